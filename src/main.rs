@@ -1,8 +1,9 @@
 use std::{path::Path, process::exit};
 
+use chrono::NaiveDate;
 use clap::Parser;
 use table_configs::paths::{self, get_config_path, get_root_dir_path, init};
-use table_maker::create_table;
+use table_maker::{create_table, regenerate_table, TableMode};
 
 #[derive(Parser)]
 #[clap(author, about, long_about = None)]
@@ -15,6 +16,30 @@ struct Cli {
     #[clap(short, long)]
     parse: bool,
 
+    ///Fetch the holidays again instead of using the cached ones. Used with --create.
+    #[clap(long)]
+    refresh_holidays: bool,
+
+    ///Start the new table the day after the current one ends and carry on its rotation. Used with --create.
+    #[clap(long = "continue", conflicts_with = "extend")]
+    continue_table: bool,
+
+    ///Append N workdays to the current table, keeping its rows and the edits made to them.
+    #[clap(long, value_name = "N")]
+    extend: Option<usize>,
+
+    ///Schedule the dates after YYYY-MM-DD again with the current names files. Earlier dates are kept.
+    #[clap(long, value_name = "YYYY-MM-DD")]
+    regenerate: Option<NaiveDate>,
+
+    ///Keep the switches made on the regenerated dates where the people are still listed. Used with --regenerate.
+    #[clap(long)]
+    keep_swaps: bool,
+
+    ///Log the messages to the console and to dry_run.log next to the table instead of sending them. Used with --parse.
+    #[clap(long)]
+    dry_run: bool,
+
     ///Clean config files from their folders. Run this when you want to uninstall.
     #[clap(short, long)]
     remove: bool,
@@ -37,34 +62,48 @@ fn main() {
         }
     }
     let cli = Cli::parse();
-    if cli.create && cli.parse {
+    let create = cli.create || cli.extend.is_some() || cli.regenerate.is_some();
+    if create && cli.parse {
         eprintln!("Invalid arguments");
         std::process::exit(1);
     }
-    if cli.remove && !(cli.create || cli.parse) {
-        std::fs::remove_dir_all(&paths::get_root_dir_path()).expect(&format!(
-            "Could not remove config files from: {}",
-            &paths::get_root_dir_path()
-        ));
+    if cli.remove && !(create || cli.parse) {
+        std::fs::remove_dir_all(paths::get_root_dir_path()).unwrap_or_else(|_| {
+            panic!(
+                "Could not remove config files from: {}",
+                &paths::get_root_dir_path()
+            )
+        });
         println!(
             "Files removed successfully from: {}",
             &paths::get_root_dir_path()
         );
         exit(0);
     }
-    if cli.create {
-        let table = match create_table(true) {
+    if create {
+        let mode = if let Some(days) = cli.extend {
+            TableMode::Extend(days)
+        } else if cli.continue_table {
+            TableMode::Continue
+        } else {
+            TableMode::New
+        };
+        let table = match cli.regenerate {
+            Some(cutoff) => regenerate_table(cutoff, cli.keep_swaps, cli.refresh_holidays),
+            None => create_table(true, cli.refresh_holidays, mode),
+        };
+        let table = match table {
             Ok(x) => x,
             Err(e) => {
                 if e.is::<std::io::Error>() {
                     panic!("Could not read or write to one of the files. Check for missing files.\n{:?}", e)
                 }
-                panic!("{:?}", e)
+                panic!("{}", e)
             }
         };
         println!("{}", &table);
     } else {
-        match table_reader::start_interface() {
+        match table_reader::start_interface(cli.dry_run) {
             Ok(_) => {}
             Err(e) => {
                 if e.is::<std::io::Error>() {
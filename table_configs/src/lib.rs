@@ -6,8 +6,23 @@ mod templates {
         "send_time":"HH:MM:SS",
         "reset_time":"HH:MM:SS",
         "maintainer":"phone_number",
+        "maintainer_chat_id":"chat_id",//optional. Telegram chat or Matrix room of the maintainer alert when the transport is a chat bot
         "alert_day":5, //1=Sunday 7=Saturday
         "weekend":[x,y,z],//1=Sunday 7=Saturday[6,7]=Friday and Saturday
+        "weekend_overrides":[{"from":"YYYY-MM-DD","to":"YYYY-MM-DD","weekend":[x,y]}],//optional. Replaces "weekend" between the given dates (inclusive)
+        "holiday_sources":[{"source":"hebcal"}],//optional. Sources are combined. "hebcal" (optional "base_url"), "offline", "file" (hebcal json, optional "path"), "csv" (title,date columns, optional "path"), "ics" ("path")
+        "holiday_cache_days":30,//optional. Holidays fetched from hebcal are reused for this many days
+        "people_per_day":1,//optional. Number of people assigned to every date
+        "people_per_weekday":{"1":2},//optional. Replaces "people_per_day" on the given weekdays. 1=Sunday 7=Saturday
+        "scheduler":{"type":"round_robin"},//optional. "round_robin" or "solver", a local search balancing fairness, spacing and preferences. Solver options: "seed" (the same seed gives the same table) and "iterations" (default 20000)
+        "shuffle":false,//optional. Shuffle the rotation order instead of sorting people by name
+        "seed":42,//optional. Seed of the shuffle. Without it a new one is picked, the seed used is written to output/table_metadata.json
+        "rotate_pairs":false,//optional. With several people per day, pair everyone with everyone in turn. Pairing rules are in config/pairing.csv
+        "rotate_weekdays":false,//optional. Move people across weekdays instead of keeping them on the same weekday
+        "tasks":[{"name":"kitchen","roster":"kitchen.csv","frequency":"daily","message":"text"}],//optional. Every task has its own rotation. roster: names file in the config folder (default names.csv). frequency: "daily", "weekly" or {"every":N} workdays. message: optional reminder text
+        "holiday_policy":{"erev":"block","chol_hamoed":"block","span":false},//optional. erev/chol_hamoed: "block", "short" (short workday) or "work". span: block every day of multi-day holidays
+        "transport":{"type":"sms4free"},//optional. How reminders are sent. "sms4free" (optional "base_url", e.g. a local mock) uses key, user, pass and sender below. "webhook" POSTs JSON to any URL: {"type":"webhook","url":"https://chat.example.com/hook","payload":{"text":"{{message}}"},"headers":{"X-Source":"cleaning"},"auth":{"type":"bearer","token":"secret"}}. {{name}}, {{phone}}, {{email}}, {{date}}, {{task}} and {{message}} in the payload's strings are replaced. auth: "bearer" with a token or "basic" with username and password. Chat bots send to the "chat_id" column of names.csv: {"type":"telegram","token":"bot token"} (optional "base_url") or {"type":"matrix","base_url":"https://matrix.example.org","access_token":"token"} with room IDs as chat IDs
+        "email":{"host":"smtp.example.com","port":587,"security":"starttls","username":"user","password":"pass","from":"cleaning@example.com","subject":"Cleaning reminder"},//optional. SMTP server for people whose "channel" in names.csv is "email" or "both". security: "starttls", "tls" or "plain"
         "key": "Insert key here",
         "user": "Insert your account's registered phone number here",
         "pass": "Insert accounts's password key here",
@@ -20,12 +35,25 @@ Maddy,972541235467
 Kaladin,972468578448
 "#;
 
-    pub const EXCLUDED_HOLIDAYS_TEMPLATE: &str = r#"names
-Purim
-Yom HaShoah
-Yom HaZikaron
-Pesach Sheni
-Yom Yerushalayim"#;
+    pub const CLOSURES_TEMPLATE: &str = r#"title,date
+"#;
+
+    //from/to: optional dates (inclusive). weekdays: optional, space separated. 1=Sunday 7=Saturday
+    pub const UNAVAILABILITY_TEMPLATE: &str = r#"name,from,to,weekdays
+"#;
+
+    //rule: together (always on the same dates) or apart (never on the same date)
+    pub const PAIRING_TEMPLATE: &str = r#"rule,first,second
+"#;
+
+    //rule: exact (default), contains, regex or category (major, minor, modern, fast).
+    //date: optional, only exclude the holiday on that date.
+    pub const EXCLUDED_HOLIDAYS_TEMPLATE: &str = r#"names,rule,date
+Purim,exact,
+Yom HaShoah,exact,
+Yom HaZikaron,exact,
+Pesach Sheni,exact,
+Yom Yerushalayim,exact,"#;
 }
 
 pub mod paths {
@@ -37,6 +65,12 @@ pub mod paths {
     const EXCLUDED_DATES_PATH_WIN: &str = "config\\excluded_hebcal.csv";
     const NAMES_PATH_UNIX: &str = "config/names.csv";
     const NAMES_PATH_WIN: &str = "config\\names.csv";
+    const CLOSURES_PATH_UNIX: &str = "config/closures.csv";
+    const CLOSURES_PATH_WIN: &str = "config\\closures.csv";
+    const UNAVAILABILITY_PATH_UNIX: &str = "config/unavailability.csv";
+    const UNAVAILABILITY_PATH_WIN: &str = "config\\unavailability.csv";
+    const PAIRING_PATH_UNIX: &str = "config/pairing.csv";
+    const PAIRING_PATH_WIN: &str = "config\\pairing.csv";
     const HEBDATE_PATH_UNIX: &str = "config/heb_date.json";
     const HEBDATE_PATH_WIN: &str = "config\\heb_date.json";
     const CONFIG_PATH_UNIX: &str = "config/config.json";
@@ -89,6 +123,46 @@ pub mod paths {
             panic!()
         }
     }
+    pub fn get_closures_path() -> String {
+        if cfg!(windows) {
+            format!("{}{}", get_app_dir(), CLOSURES_PATH_WIN)
+        } else if cfg!(unix) {
+            format!("{}{}", get_app_dir(), CLOSURES_PATH_UNIX)
+        } else {
+            panic!()
+        }
+    }
+    pub fn get_unavailability_path() -> String {
+        if cfg!(windows) {
+            format!("{}{}", get_app_dir(), UNAVAILABILITY_PATH_WIN)
+        } else if cfg!(unix) {
+            format!("{}{}", get_app_dir(), UNAVAILABILITY_PATH_UNIX)
+        } else {
+            panic!()
+        }
+    }
+    pub fn get_pairing_path() -> String {
+        if cfg!(windows) {
+            format!("{}{}", get_app_dir(), PAIRING_PATH_WIN)
+        } else if cfg!(unix) {
+            format!("{}{}", get_app_dir(), PAIRING_PATH_UNIX)
+        } else {
+            panic!()
+        }
+    }
+    ///Names file of a task. Relative paths are looked up in the config folder.
+    pub fn get_roster_path(file: &str) -> String {
+        if Path::new(file).is_absolute() {
+            return file.to_string();
+        }
+        if cfg!(windows) {
+            format!("{}config\\{}", get_app_dir(), file)
+        } else if cfg!(unix) {
+            format!("{}config/{}", get_app_dir(), file)
+        } else {
+            panic!()
+        }
+    }
     pub fn get_config_path() -> String {
         if cfg!(windows) {
             format!("{}{}", get_app_dir(), CONFIG_PATH_WIN)
@@ -107,6 +181,10 @@ pub mod paths {
             panic!()
         }
     }
+    ///Log of the messages of a dry run, next to the table.
+    pub fn get_dry_run_log_path() -> String {
+        get_output_path("dry_run.log")
+    }
     pub fn init() -> Result<bool, Box<dyn std::error::Error>> {
         let mut all_init = true;
         all_init =
@@ -117,6 +195,13 @@ pub mod paths {
             &get_excluded_holidays_path(),
             templates::EXCLUDED_HOLIDAYS_TEMPLATE,
         )? && all_init;
+        //Optional files, empty ones don't require the user's attention
+        create_if_doesnt_exists(&get_closures_path(), templates::CLOSURES_TEMPLATE)?;
+        create_if_doesnt_exists(
+            &get_unavailability_path(),
+            templates::UNAVAILABILITY_TEMPLATE,
+        )?;
+        create_if_doesnt_exists(&get_pairing_path(), templates::PAIRING_TEMPLATE)?;
         std::fs::create_dir_all(get_output_path(""))?;
         Ok(all_init)
     }
@@ -139,9 +224,10 @@ pub mod paths {
 }
 pub mod config {
     use crate::paths;
-    use chrono::{NaiveDate, NaiveTime};
+    use chrono::{Datelike, NaiveDate, NaiveTime};
     use serde::Deserialize;
     use serde_json;
+    use std::collections::{BTreeMap, HashMap};
 
     pub fn load_config() -> Config {
         let config =
@@ -160,8 +246,38 @@ pub mod config {
         pub send_time: String,
         pub reset_time: String,
         pub maintainer: String,
+        #[serde(default)]
+        pub maintainer_chat_id: String,
         pub alert_day: usize,
         pub weekend: Vec<usize>,
+        #[serde(default)]
+        pub weekend_overrides: Vec<WeekendOverrideRaw>,
+        #[serde(default = "default_holiday_sources")]
+        pub holiday_sources: Vec<HolidaySource>,
+        #[serde(default = "default_holiday_cache_days")]
+        pub holiday_cache_days: i64,
+        #[serde(default)]
+        pub holiday_policy: HolidayPolicy,
+        #[serde(default = "default_people_per_day")]
+        pub people_per_day: usize,
+        #[serde(default)]
+        pub people_per_weekday: HashMap<usize, usize>,
+        #[serde(default)]
+        pub scheduler: SchedulerKind,
+        #[serde(default)]
+        pub rotate_weekdays: bool,
+        #[serde(default)]
+        pub rotate_pairs: bool,
+        #[serde(default)]
+        pub shuffle: bool,
+        #[serde(default)]
+        pub seed: Option<u64>,
+        #[serde(default)]
+        pub tasks: Vec<Task>,
+        #[serde(default)]
+        pub transport: TransportKind,
+        #[serde(default)]
+        pub email: Option<EmailConfig>,
         pub key: String,
         pub user: String,
         pub pass: String,
@@ -176,8 +292,22 @@ pub mod config {
         pub send_time: NaiveTime,
         pub reset_time: NaiveTime,
         pub maintainer: String,
+        pub maintainer_chat_id: String,
         pub alert_day: chrono::Weekday,
         pub weekend: Vec<chrono::Weekday>,
+        pub weekend_overrides: Vec<WeekendOverride>,
+        pub holiday_sources: Vec<HolidaySource>,
+        pub holiday_cache_days: i64,
+        pub holiday_policy: HolidayPolicy,
+        pub staffing: Staffing,
+        pub scheduler: SchedulerKind,
+        pub rotate_weekdays: bool,
+        pub rotate_pairs: bool,
+        pub shuffle: bool,
+        pub seed: Option<u64>,
+        pub tasks: Vec<Task>,
+        pub transport: TransportKind,
+        pub email: Option<EmailConfig>,
         pub key: String,
         pub user: String,
         pub pass: String,
@@ -192,8 +322,38 @@ pub mod config {
                 send_time: NaiveTime::parse_from_str(&config.send_time, "%H:%M:%S").unwrap(),
                 reset_time: NaiveTime::parse_from_str(&config.reset_time, "%H:%M:%S").unwrap(),
                 maintainer: config.maintainer,
+                maintainer_chat_id: config.maintainer_chat_id,
                 alert_day: int_to_weekday(config.alert_day),
                 weekend: config.weekend.iter().map(|x| int_to_weekday(*x)).collect(),
+                weekend_overrides: config
+                    .weekend_overrides
+                    .iter()
+                    .map(WeekendOverride::from)
+                    .collect(),
+                holiday_sources: config.holiday_sources,
+                holiday_cache_days: config.holiday_cache_days,
+                holiday_policy: config.holiday_policy,
+                staffing: Staffing {
+                    per_day: config.people_per_day,
+                    per_weekday: config
+                        .people_per_weekday
+                        .iter()
+                        .map(|(day, count)| (int_to_weekday(*day), *count))
+                        .collect(),
+                },
+                scheduler: config.scheduler,
+                rotate_weekdays: config.rotate_weekdays,
+                rotate_pairs: config.rotate_pairs,
+                shuffle: config.shuffle,
+                seed: config.seed,
+                //Without tasks the table has the single cleaning task of older configs
+                tasks: if config.tasks.is_empty() {
+                    vec![Task::default()]
+                } else {
+                    config.tasks
+                },
+                transport: config.transport,
+                email: config.email,
                 key: config.key,
                 user: config.user,
                 pass: config.pass,
@@ -201,6 +361,284 @@ pub mod config {
             }
         }
     }
+    ///Number of people assigned to a date.
+    #[derive(Debug, Clone)]
+    pub struct Staffing {
+        pub per_day: usize,
+        pub per_weekday: HashMap<chrono::Weekday, usize>,
+    }
+    impl Staffing {
+        pub fn people_on(&self, date: &NaiveDate) -> usize {
+            *self
+                .per_weekday
+                .get(&date.weekday())
+                .unwrap_or(&self.per_day)
+        }
+    }
+    impl Default for Staffing {
+        fn default() -> Self {
+            Self {
+                per_day: 1,
+                per_weekday: HashMap::new(),
+            }
+        }
+    }
+
+    ///Name of the task of tables and configs that don't define tasks.
+    pub const DEFAULT_TASK: &str = "cleaning";
+
+    ///A chore with its own rotation and reminder.
+    #[derive(Deserialize, Debug, Clone)]
+    pub struct Task {
+        pub name: String,
+        ///Names file, relative to the config folder
+        #[serde(default = "default_roster")]
+        pub roster: String,
+        #[serde(default)]
+        pub frequency: Frequency,
+        ///Reminder text, the cleaning reminder is sent when missing
+        #[serde(default)]
+        pub message: Option<String>,
+    }
+    impl Default for Task {
+        fn default() -> Self {
+            Self {
+                name: DEFAULT_TASK.to_string(),
+                roster: default_roster(),
+                frequency: Frequency::Daily,
+                message: None,
+            }
+        }
+    }
+
+    ///Service sending the reminders.
+    #[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+    #[serde(tag = "type", rename_all = "snake_case")]
+    pub enum TransportKind {
+        ///SMS through sms4free.co.il, using the key, user, pass and sender fields
+        Sms4free {
+            #[serde(default = "default_sms4free_url")]
+            base_url: String,
+        },
+        ///Messages from a Telegram bot to the "chat_id" of every person
+        Telegram {
+            token: String,
+            #[serde(default = "default_telegram_url")]
+            base_url: String,
+        },
+        ///Messages from a Matrix account to the room in the "chat_id" of every person
+        Matrix {
+            ///Homeserver of the account
+            base_url: String,
+            access_token: String,
+        },
+        ///POST of a JSON payload to any URL
+        Webhook {
+            url: String,
+            ///Placeholders like "{{message}}" in its strings are filled in for every reminder
+            #[serde(default = "default_webhook_payload")]
+            payload: serde_json::Value,
+            #[serde(default)]
+            headers: BTreeMap<String, String>,
+            #[serde(default)]
+            auth: Option<WebhookAuth>,
+        },
+    }
+    impl Default for TransportKind {
+        fn default() -> Self {
+            Self::Sms4free {
+                base_url: default_sms4free_url(),
+            }
+        }
+    }
+
+    ///Credentials of a webhook.
+    #[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+    #[serde(tag = "type", rename_all = "snake_case")]
+    pub enum WebhookAuth {
+        Bearer {
+            token: String,
+        },
+        Basic {
+            username: String,
+            #[serde(default)]
+            password: String,
+        },
+    }
+
+    ///SMTP server sending email reminders.
+    #[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct EmailConfig {
+        pub host: String,
+        #[serde(default = "default_smtp_port")]
+        pub port: u16,
+        #[serde(default)]
+        pub security: SmtpSecurity,
+        ///No authentication when empty
+        #[serde(default)]
+        pub username: String,
+        #[serde(default)]
+        pub password: String,
+        pub from: String,
+        #[serde(default = "default_email_subject")]
+        pub subject: String,
+    }
+    ///How the connection to the SMTP server is encrypted.
+    #[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[serde(rename_all = "lowercase")]
+    pub enum SmtpSecurity {
+        ///Upgrade a plain connection with STARTTLS
+        #[default]
+        Starttls,
+        ///TLS from the start, usually on port 465
+        Tls,
+        ///No encryption, e.g. a local SMTP sink
+        Plain,
+    }
+
+    ///How people are assigned to the dates of a task.
+    #[derive(Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+    #[serde(tag = "type", rename_all = "snake_case")]
+    pub enum SchedulerKind {
+        ///Rotation in roster order
+        #[default]
+        RoundRobin,
+        ///Local search improving the rotation
+        Solver {
+            #[serde(default)]
+            seed: u64,
+            #[serde(default = "default_solver_iterations")]
+            iterations: usize,
+        },
+    }
+
+    ///Which workdays a task is scheduled on.
+    #[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[serde(rename_all = "lowercase")]
+    pub enum Frequency {
+        #[default]
+        Daily,
+        ///First workday of every week
+        Weekly,
+        ///Every N workdays
+        Every(usize),
+    }
+
+    #[derive(Deserialize)]
+    pub struct WeekendOverrideRaw {
+        pub from: String,
+        pub to: String,
+        pub weekend: Vec<usize>,
+    }
+
+    ///Different set of weekend days for a range of dates, e.g. a summer schedule.
+    #[derive(Debug, Clone)]
+    pub struct WeekendOverride {
+        pub from: NaiveDate,
+        pub to: NaiveDate,
+        pub weekend: Vec<chrono::Weekday>,
+    }
+    impl WeekendOverride {
+        pub fn from(raw: &WeekendOverrideRaw) -> Self {
+            Self {
+                from: NaiveDate::parse_from_str(&raw.from, "%Y-%m-%d")
+                    .expect("weekend_overrides has a wrong \"from\" date. Expecting YYYY-MM-DD."),
+                to: NaiveDate::parse_from_str(&raw.to, "%Y-%m-%d")
+                    .expect("weekend_overrides has a wrong \"to\" date. Expecting YYYY-MM-DD."),
+                weekend: raw.weekend.iter().map(|x| int_to_weekday(*x)).collect(),
+            }
+        }
+    }
+
+    ///Where holidays are taken from. Every listed source is combined into one list.
+    #[derive(Deserialize, Debug, Clone)]
+    #[serde(tag = "source", rename_all = "lowercase")]
+    pub enum HolidaySource {
+        Hebcal {
+            #[serde(default = "default_hebcal_url")]
+            base_url: String,
+        },
+        Offline,
+        File {
+            #[serde(default = "paths::get_hebdate_path")]
+            path: String,
+        },
+        Csv {
+            #[serde(default = "paths::get_closures_path")]
+            path: String,
+        },
+        Ics {
+            path: String,
+        },
+    }
+    ///What a day of a holiday is to the table.
+    #[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[serde(rename_all = "lowercase")]
+    pub enum DayPolicy {
+        ///Nobody is scheduled
+        #[default]
+        Block,
+        ///Scheduled and marked as a short day
+        Short,
+        ///Scheduled as a regular day
+        Work,
+    }
+
+    ///How holiday eves and multi-day holidays are handled.
+    #[derive(Deserialize, Debug, Clone, Default)]
+    pub struct HolidayPolicy {
+        #[serde(default)]
+        pub erev: DayPolicy,
+        #[serde(default)]
+        pub chol_hamoed: DayPolicy,
+        ///Block every day between the first and last day of Sukkot, Pesach and Chanukah
+        #[serde(default)]
+        pub span: bool,
+    }
+
+    fn default_roster() -> String {
+        "names.csv".to_string()
+    }
+    fn default_smtp_port() -> u16 {
+        587
+    }
+    fn default_email_subject() -> String {
+        "Cleaning reminder".to_string()
+    }
+    fn default_sms4free_url() -> String {
+        "https://api.sms4free.co.il".to_string()
+    }
+    fn default_telegram_url() -> String {
+        "https://api.telegram.org".to_string()
+    }
+    fn default_webhook_payload() -> serde_json::Value {
+        serde_json::json!({
+            "name": "{{name}}",
+            "phone": "{{phone}}",
+            "email": "{{email}}",
+            "date": "{{date}}",
+            "task": "{{task}}",
+            "message": "{{message}}",
+        })
+    }
+    fn default_solver_iterations() -> usize {
+        20000
+    }
+    fn default_people_per_day() -> usize {
+        1
+    }
+    fn default_hebcal_url() -> String {
+        "https://www.hebcal.com".to_string()
+    }
+    fn default_holiday_cache_days() -> i64 {
+        30
+    }
+    fn default_holiday_sources() -> Vec<HolidaySource> {
+        vec![HolidaySource::Hebcal {
+            base_url: default_hebcal_url(),
+        }]
+    }
+
     //sunday=1,saturday = 7
     pub fn int_to_weekday(i: usize) -> chrono::Weekday {
         use chrono::Weekday;
@@ -0,0 +1,105 @@
+use chrono::{Datelike, NaiveDate, Weekday};
+use table_configs::config::{Config, WeekendOverride};
+
+///Working-day calendar shared by table generation and every table modification.
+#[derive(Debug, Clone)]
+pub struct WorkCalendar {
+    weekend: Vec<Weekday>,
+    overrides: Vec<WeekendOverride>,
+}
+
+impl WorkCalendar {
+    pub fn new(weekend: Vec<Weekday>, overrides: Vec<WeekendOverride>) -> Self {
+        Self { weekend, overrides }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.weekend.clone(), config.weekend_overrides.clone())
+    }
+
+    //First matching override wins, otherwise fall back to the default weekend.
+    pub fn weekend_on(&self, date: &NaiveDate) -> &Vec<Weekday> {
+        self.overrides
+            .iter()
+            .find(|o| o.from <= *date && *date <= o.to)
+            .map(|o| &o.weekend)
+            .unwrap_or(&self.weekend)
+    }
+
+    pub fn is_workday(&self, date: &NaiveDate) -> bool {
+        !self.weekend_on(date).contains(&date.weekday())
+    }
+
+    ///Iterate over all working days starting from, and including, `start_date`. When the weekend
+    ///covers every weekday the days end with the last override.
+    pub fn workdays_from<'a>(
+        &'a self,
+        start_date: &NaiveDate,
+    ) -> impl Iterator<Item = NaiveDate> + 'a {
+        let every_day = (0..7).all(|n| self.weekend.iter().any(|d| d.num_days_from_monday() == n));
+        let last = if !every_day {
+            NaiveDate::MAX
+        } else {
+            self.overrides
+                .iter()
+                .map(|o| o.to)
+                .max()
+                .unwrap_or(*start_date)
+        };
+        start_date
+            .iter_days()
+            .take_while(move |d| *d <= last)
+            .filter(move |d| self.is_workday(d))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn override_replaces_weekend() {
+        let calendar = WorkCalendar::new(
+            vec![Weekday::Fri, Weekday::Sat],
+            vec![WeekendOverride {
+                from: NaiveDate::from_ymd_opt(2022, 7, 1).unwrap(),
+                to: NaiveDate::from_ymd_opt(2022, 8, 31).unwrap(),
+                weekend: vec![Weekday::Thu, Weekday::Fri, Weekday::Sat],
+            }],
+        );
+        //Thursdays
+        assert!(calendar.is_workday(&NaiveDate::from_ymd_opt(2022, 6, 30).unwrap()));
+        assert!(!calendar.is_workday(&NaiveDate::from_ymd_opt(2022, 7, 7).unwrap()));
+        assert!(!calendar.is_workday(&NaiveDate::from_ymd_opt(2022, 8, 25).unwrap()));
+        assert!(calendar.is_workday(&NaiveDate::from_ymd_opt(2022, 9, 1).unwrap()));
+        //Fridays are off in both
+        assert!(!calendar.is_workday(&NaiveDate::from_ymd_opt(2022, 6, 24).unwrap()));
+        assert!(!calendar.is_workday(&NaiveDate::from_ymd_opt(2022, 7, 8).unwrap()));
+    }
+
+    #[test]
+    fn weekend_without_workdays_ends() {
+        let week = vec![
+            Weekday::Sun,
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+            Weekday::Sat,
+        ];
+        let start = NaiveDate::from_ymd_opt(2022, 7, 1).unwrap();
+        let calendar = WorkCalendar::new(week.clone(), vec![]);
+        assert_eq!(calendar.workdays_from(&start).count(), 0);
+
+        let calendar = WorkCalendar::new(
+            week,
+            vec![WeekendOverride {
+                from: start,
+                to: NaiveDate::from_ymd_opt(2022, 7, 14).unwrap(),
+                weekend: vec![Weekday::Fri, Weekday::Sat],
+            }],
+        );
+        assert_eq!(calendar.workdays_from(&start).count(), 10);
+    }
+}
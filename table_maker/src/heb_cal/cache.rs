@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use super::{HebDate, HebDateRaw};
+
+///When fetched holidays may be reused instead of fetching them again.
+#[derive(Debug, Clone)]
+pub struct CachePolicy {
+    pub path: String,
+    ///Entries older than this number of days are fetched again.
+    pub max_age_days: i64,
+    ///Ignore fresh entries and fetch everything again.
+    pub refresh: bool,
+}
+
+///Content of heb_date.json. Holidays fetched from each source, per Gregorian year.
+#[derive(Serialize, Deserialize, Default)]
+pub struct HolidayCache {
+    #[serde(default)]
+    sources: HashMap<String, HashMap<i32, CacheEntry>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    fetched: String,
+    items: Vec<HebDateRaw>,
+}
+
+impl HolidayCache {
+    ///Load the cache, an unreadable or missing file is treated as an empty cache.
+    pub fn load(path: &str) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    ///Cached holidays of a source for a year and the date they were fetched on.
+    pub fn get(&self, source: &str, year: i32) -> Option<(NaiveDate, Vec<HebDate>)> {
+        let entry = self.sources.get(source)?.get(&year)?;
+        let fetched = NaiveDate::parse_from_str(&entry.fetched, "%Y-%m-%d").ok()?;
+        Some((fetched, entry.items.iter().map(HebDate::from).collect()))
+    }
+
+    ///Cached holidays of a year from every source.
+    pub fn get_any(&self, year: i32) -> Vec<HebDate> {
+        self.sources
+            .values()
+            .filter_map(|years| years.get(&year))
+            .flat_map(|entry| entry.items.iter().map(HebDate::from))
+            .collect()
+    }
+
+    pub fn insert(&mut self, source: &str, year: i32, fetched: NaiveDate, items: &[HebDate]) {
+        self.sources.entry(source.to_string()).or_default().insert(
+            year,
+            CacheEntry {
+                fetched: fetched.format("%Y-%m-%d").to_string(),
+                items: items.iter().map(HebDateRaw::from).collect(),
+            },
+        );
+    }
+}
+
+impl CachePolicy {
+    pub fn is_fresh(&self, fetched: &NaiveDate, today: &NaiveDate) -> bool {
+        !self.refresh && (*today - *fetched).num_days() < self.max_age_days
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heb_cal::hebrew;
+    use crate::heb_cal::provider::{HebcalProvider, HolidayProvider};
+    use fake_server::{serve, unreachable};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn closure() -> HebDate {
+        HebDate {
+            title: "Closure".to_string(),
+            date: date(2022, 6, 1),
+            category: None,
+        }
+    }
+
+    fn provider(base_url: &str, path: &str, refresh: bool) -> HebcalProvider {
+        HebcalProvider {
+            base_url: base_url.to_string(),
+            policy: CachePolicy {
+                path: path.to_string(),
+                max_age_days: 30,
+                refresh,
+            },
+        }
+    }
+
+    //Holidays of 2022 from the provider, with `cached` in its cache file beforehand
+    fn holidays_2022(provider: &HebcalProvider, cached: Option<NaiveDate>) -> Vec<HebDate> {
+        let path = &provider.policy.path;
+        if let Some(fetched) = cached {
+            let mut cache = HolidayCache::default();
+            cache.insert(&provider.name(), 2022, fetched, &[closure()]);
+            cache.save(path).unwrap();
+        }
+        let items = provider.holidays(&date(2022, 1, 1), &date(2022, 12, 31));
+        std::fs::remove_file(path).unwrap();
+        items.unwrap()
+    }
+
+    #[test]
+    fn cache_round_trip() {
+        let path = "./test_heb_date.json";
+        let fetched = NaiveDate::from_ymd_opt(2022, 5, 1).unwrap();
+        let mut cache = HolidayCache::default();
+        cache.insert(
+            "hebcal",
+            2022,
+            fetched,
+            &[HebDate {
+                title: "Purim".to_string(),
+                date: NaiveDate::from_ymd_opt(2022, 3, 17).unwrap(),
+                category: Some("major".to_string()),
+            }],
+        );
+        cache.save(path).unwrap();
+        let cache = HolidayCache::load(path);
+        std::fs::remove_file(path).unwrap();
+
+        let (date, items) = cache.get("hebcal", 2022).unwrap();
+        assert_eq!(date, fetched);
+        assert_eq!(items[0].title, "Purim");
+        assert!(cache.get("hebcal", 2023).is_none());
+        assert!(cache.get("offline", 2022).is_none());
+
+        let policy = CachePolicy {
+            path: path.to_string(),
+            max_age_days: 30,
+            refresh: false,
+        };
+        assert!(policy.is_fresh(&fetched, &NaiveDate::from_ymd_opt(2022, 5, 30).unwrap()));
+        assert!(!policy.is_fresh(&fetched, &NaiveDate::from_ymd_opt(2022, 6, 1).unwrap()));
+        let policy = CachePolicy {
+            refresh: true,
+            ..policy
+        };
+        assert!(!policy.is_fresh(&fetched, &fetched));
+    }
+
+    #[test]
+    fn failed_fetch_uses_cache() {
+        let provider = provider(&unreachable(), "./test_cache_stale.json", false);
+        let items = holidays_2022(&provider, Some(date(2000, 1, 1)));
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, "Closure");
+    }
+
+    #[test]
+    fn failed_fetch_without_cache_is_offline() {
+        let provider = provider(&unreachable(), "./test_cache_missing.json", false);
+        let mut offline = hebrew::holidays_for_year(2022);
+        offline.sort_by_key(|h| h.date);
+        let mut items = holidays_2022(&provider, None);
+        items.sort_by_key(|h| h.date);
+        assert_eq!(
+            items.iter().map(|h| (&h.title, h.date)).collect::<Vec<_>>(),
+            offline
+                .iter()
+                .map(|h| (&h.title, h.date))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn reply_without_items_uses_cache() {
+        let (base_url, handle) = serve(vec![("200 OK", "{}"), ("200 OK", "{}")]);
+        let provider = provider(&base_url, "./test_cache_no_items.json", false);
+        let items = holidays_2022(&provider, Some(date(2000, 1, 1)));
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, "Closure");
+
+        let mut offline = hebrew::holidays_for_year(2022);
+        offline.sort_by_key(|h| h.date);
+        let mut items = holidays_2022(&provider, None);
+        items.sort_by_key(|h| h.date);
+        assert_eq!(
+            items.iter().map(|h| &h.title).collect::<Vec<_>>(),
+            offline.iter().map(|h| &h.title).collect::<Vec<_>>()
+        );
+        assert_eq!(handle.join().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn error_status_uses_cache() {
+        let json = r#"{"items":[{"title":"Purim","date":"2022-03-17"}]}"#;
+        let (base_url, handle) = serve(vec![("503 Service Unavailable", json)]);
+        let provider = provider(&base_url, "./test_cache_error_status.json", false);
+        let items = holidays_2022(&provider, Some(date(2000, 1, 1)));
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, "Closure");
+        assert_eq!(handle.join().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn fresh_cache_is_reused() {
+        let today = chrono::Local::now().date_naive();
+        let provider = provider(&unreachable(), "./test_cache_fresh.json", false);
+        let items = holidays_2022(&provider, Some(today));
+        assert_eq!(items[0].title, "Closure");
+    }
+
+    #[test]
+    fn refresh_bypasses_fresh_cache() {
+        let json = r#"{"items":[{"title":"Purim","date":"2022-03-17","subcat":"major"}]}"#;
+        let (base_url, handle) = serve(vec![("200 OK", json)]);
+        let today = chrono::Local::now().date_naive();
+        let provider = provider(&base_url, "./test_cache_refresh.json", true);
+        let items = holidays_2022(&provider, Some(today));
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, "Purim");
+        assert_eq!(handle.join().unwrap().len(), 1);
+    }
+}
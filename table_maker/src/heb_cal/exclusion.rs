@@ -0,0 +1,226 @@
+use std::fmt::Display;
+
+use chrono::NaiveDate;
+use csv::Reader;
+use regex::Regex;
+use serde::Deserialize;
+
+use super::HebDate;
+
+//Row of excluded_hebcal.csv. Only "names" is required, older files contain nothing else.
+#[derive(Deserialize)]
+struct ExcludedName {
+    pub names: String,
+    #[serde(default)]
+    pub rule: String,
+    #[serde(default)]
+    pub date: String,
+}
+
+enum Matcher {
+    Exact(String),
+    Contains(String),
+    Regex(Regex),
+    Category(String),
+}
+
+///Holidays matched by an exclusion rule are not skipped when the table is generated.
+pub struct ExclusionRule {
+    matcher: Matcher,
+    //Only remove the holiday on this date
+    date: Option<NaiveDate>,
+}
+
+impl ExclusionRule {
+    fn from(row: &ExcludedName) -> Result<Self, Box<dyn std::error::Error>> {
+        let names = row.names.trim().to_string();
+        let matcher = match row.rule.trim() {
+            "" | "exact" => Matcher::Exact(names),
+            "contains" => Matcher::Contains(names),
+            "regex" => Matcher::Regex(Regex::new(&names)?),
+            "category" => Matcher::Category(names.to_lowercase()),
+            other => {
+                return Err(format!(
+                    "Unknown rule \"{}\" for \"{}\" in excluded holidays file. Expecting exact, contains, regex or category.",
+                    other, names
+                )
+                .into())
+            }
+        };
+        let date = match row.date.trim() {
+            "" => None,
+            date => Some(NaiveDate::parse_from_str(date, "%Y-%m-%d")?),
+        };
+        Ok(Self { matcher, date })
+    }
+
+    fn matches_title(&self, holiday: &HebDate) -> bool {
+        match &self.matcher {
+            Matcher::Exact(name) => holiday.title == *name,
+            Matcher::Contains(name) => holiday.title.contains(name.as_str()),
+            Matcher::Regex(regex) => regex.is_match(&holiday.title),
+            Matcher::Category(category) => holiday.category.as_deref() == Some(category.as_str()),
+        }
+    }
+
+    fn matches_date(&self, holiday: &HebDate) -> bool {
+        self.date.is_none_or(|date| date == holiday.date)
+    }
+}
+
+impl Display for ExclusionRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.matcher {
+            Matcher::Exact(name) => write!(f, "exact \"{}\"", name)?,
+            Matcher::Contains(name) => write!(f, "contains \"{}\"", name)?,
+            Matcher::Regex(regex) => write!(f, "regex \"{}\"", regex)?,
+            Matcher::Category(category) => write!(f, "category \"{}\"", category)?,
+        }
+        if let Some(date) = self.date {
+            write!(f, " on {}", date)?;
+        }
+        Ok(())
+    }
+}
+
+///Which holidays every rule removed, and which ones matched its name but were kept
+///because of the rule's date.
+pub struct ExclusionReport {
+    lines: Vec<(String, Vec<String>, Vec<String>)>,
+}
+
+impl Display for ExclusionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Excluded holidays:")?;
+        for (rule, removed, kept) in &self.lines {
+            if removed.is_empty() && kept.is_empty() {
+                writeln!(f, "{}: matched nothing", rule)?;
+                continue;
+            }
+            if !removed.is_empty() {
+                writeln!(f, "{}: removed {}", rule, removed.join(", "))?;
+            }
+            if !kept.is_empty() {
+                writeln!(f, "{}: kept {}", rule, kept.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn parse_rules(csv: &str) -> Result<Vec<ExclusionRule>, Box<dyn std::error::Error>> {
+    let mut rdr = Reader::from_reader(csv.as_bytes());
+    let mut rules = Vec::new();
+    for row in rdr.deserialize() {
+        let row: ExcludedName = row?;
+        rules.push(ExclusionRule::from(&row)?);
+    }
+    Ok(rules)
+}
+
+///Remove every holiday matched by one of the rules.
+pub fn apply_rules(
+    dates: Vec<HebDate>,
+    rules: &[ExclusionRule],
+) -> (Vec<HebDate>, ExclusionReport) {
+    let describe = |h: &HebDate| format!("{} ({})", h.title, h.date);
+    let mut lines = Vec::new();
+    for rule in rules {
+        let matched: Vec<&HebDate> = dates.iter().filter(|h| rule.matches_title(h)).collect();
+        let removed = matched
+            .iter()
+            .filter(|h| rule.matches_date(h))
+            .map(|h| describe(h))
+            .collect();
+        let kept = matched
+            .iter()
+            .filter(|h| !rule.matches_date(h))
+            .map(|h| describe(h))
+            .collect();
+        lines.push((rule.to_string(), removed, kept));
+    }
+    let filtered = dates
+        .into_iter()
+        .filter(|h| {
+            !rules
+                .iter()
+                .any(|r| r.matches_title(h) && r.matches_date(h))
+        })
+        .collect();
+    (filtered, ExclusionReport { lines })
+}
+
+pub fn exclude_holidays_from_file(
+    dates: Vec<HebDate>,
+    file: &str,
+) -> Result<(Vec<HebDate>, ExclusionReport), Box<dyn std::error::Error>> {
+    let file = std::fs::read_to_string(file)?;
+    let rules = parse_rules(&file)?;
+    Ok(apply_rules(dates, &rules))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holiday(title: &str, date: (i32, u32, u32), category: &str) -> HebDate {
+        HebDate {
+            title: title.to_string(),
+            date: NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap(),
+            category: Some(category.to_string()),
+        }
+    }
+
+    #[test]
+    fn rules() {
+        let dates = vec![
+            holiday("Purim", (2022, 3, 17), "major"),
+            holiday("Shushan Purim", (2022, 3, 18), "minor"),
+            holiday("Purim Katan", (2022, 2, 15), "minor"),
+            holiday("Purim", (2023, 3, 7), "major"),
+            holiday("Yom HaShoah", (2022, 4, 28), "modern"),
+            holiday("Tzom Tammuz", (2022, 7, 17), "fast"),
+            holiday("Pesach I", (2022, 4, 16), "major"),
+        ];
+        let rules = parse_rules(
+            "names,rule,date
+Purim,exact,2023-03-07
+^Yom Ha,regex,
+Fast,category,
+Chanukah,,
+",
+        )
+        .unwrap();
+        let (filtered, report) = apply_rules(dates, &rules);
+        let titles: Vec<String> = filtered
+            .iter()
+            .map(|h| format!("{} {}", h.title, h.date))
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                "Purim 2022-03-17",
+                "Shushan Purim 2022-03-18",
+                "Purim Katan 2022-02-15",
+                "Pesach I 2022-04-16"
+            ]
+        );
+        let report = report.to_string();
+        assert!(report.contains("exact \"Purim\" on 2023-03-07: kept Purim (2022-03-17)"));
+        assert!(report.contains("exact \"Chanukah\": matched nothing"));
+    }
+
+    #[test]
+    fn names_only_file() {
+        let rules = parse_rules("names\nPurim\n").unwrap();
+        let (filtered, _) = apply_rules(
+            vec![
+                holiday("Purim", (2022, 3, 17), "major"),
+                holiday("Shushan Purim", (2022, 3, 18), "minor"),
+            ],
+            &rules,
+        );
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].title, "Shushan Purim");
+    }
+}
@@ -0,0 +1,460 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+use super::HebDate;
+
+//Months are numbered from Nisan while the year itself starts in Tishrei.
+const NISAN: u32 = 1;
+const IYAR: u32 = 2;
+const SIVAN: u32 = 3;
+const TAMMUZ: u32 = 4;
+const AV: u32 = 5;
+const ELUL: u32 = 6;
+const TISHREI: u32 = 7;
+const CHESHVAN: u32 = 8;
+const KISLEV: u32 = 9;
+const TEVET: u32 = 10;
+const SHVAT: u32 = 11;
+//Adar I on leap years
+const ADAR: u32 = 12;
+const ADAR_II: u32 = 13;
+
+//1 Tishrei of year 1, counted in days from 0001-01-01 the same way chrono does.
+const HEBREW_EPOCH: i64 = -1373427;
+
+///Compute the holidays hebcal.com returns (major, minor and modern, Israel schedule) for a
+///Gregorian year without any network access.
+pub fn holidays_for_year(year: i32) -> Vec<HebDate> {
+    let mut holidays: Vec<HebDate> = [year as i64 + 3760, year as i64 + 3761]
+        .iter()
+        .flat_map(|y| holidays_of_hebrew_year(*y))
+        .filter(|h| h.date.year() == year)
+        .collect();
+    holidays.sort_by_key(|h| h.date);
+    holidays
+}
+
+//Every holiday from 1 Tishrei to 29 Elul of the given Hebrew year, plus Erev Rosh Hashana.
+fn holidays_of_hebrew_year(year: i64) -> Vec<HebDate> {
+    let mut list = Vec::new();
+    let mut add = |title: &str, date: NaiveDate| {
+        list.push(HebDate {
+            title: title.to_string(),
+            date,
+            category: Some(category(title).to_string()),
+        })
+    };
+    let day = |month: u32, day: u32| to_gregorian(year, month, day);
+
+    //Tishrei
+    add("Erev Rosh Hashana", day(TISHREI, 1) - Duration::days(1));
+    add(&format!("Rosh Hashana {}", year), day(TISHREI, 1));
+    add("Rosh Hashana II", day(TISHREI, 2));
+    add("Tzom Gedaliah", postpone_from_shabbat(day(TISHREI, 3)));
+    add("Erev Yom Kippur", day(TISHREI, 9));
+    add("Yom Kippur", day(TISHREI, 10));
+    add("Erev Sukkot", day(TISHREI, 14));
+    add("Sukkot I", day(TISHREI, 15));
+    for (i, numeral) in ["II", "III", "IV", "V", "VI"].iter().enumerate() {
+        add(
+            &format!("Sukkot {} (CH''M)", numeral),
+            day(TISHREI, 16 + i as u32),
+        );
+    }
+    add("Sukkot VII (Hoshana Raba)", day(TISHREI, 21));
+    add("Shmini Atzeret", day(TISHREI, 22));
+
+    //Cheshvan - Shvat
+    if year >= 5758 {
+        let rabin = day(CHESHVAN, 12);
+        let rabin = match rabin.weekday() {
+            Weekday::Fri => day(CHESHVAN, 11),
+            Weekday::Sat => day(CHESHVAN, 10),
+            _ => rabin,
+        };
+        add("Yitzhak Rabin Memorial Day", rabin);
+    }
+    add("Sigd", day(CHESHVAN, 29));
+    if year >= 5737 {
+        let ben_gurion = day(KISLEV, 6);
+        let ben_gurion = match ben_gurion.weekday() {
+            Weekday::Fri => ben_gurion + Duration::days(2),
+            Weekday::Sat => ben_gurion + Duration::days(1),
+            _ => ben_gurion,
+        };
+        add("Ben-Gurion Day", ben_gurion);
+    }
+    let chanukah = day(KISLEV, 24);
+    add("Chanukah: 1 Candle", chanukah);
+    for candles in 2..=8 {
+        add(
+            &format!("Chanukah: {} Candles", candles),
+            chanukah + Duration::days(candles - 1),
+        );
+    }
+    add("Chanukah: 8th Day", chanukah + Duration::days(8));
+    add("Chag HaBanot", day(TEVET, 1));
+    add("Asara B'Tevet", day(TEVET, 10));
+    if year >= 5773 {
+        add("Hebrew Language Day", postpone_from_shabbat(day(TEVET, 21)));
+    }
+    add("Tu BiShvat", day(SHVAT, 15));
+    if year >= 5750 {
+        add("Family Day", day(SHVAT, 30));
+    }
+
+    //Adar
+    let adar = if is_leap_year(year) {
+        add("Purim Katan", day(ADAR, 14));
+        ADAR_II
+    } else {
+        ADAR
+    };
+    let taanit_esther = day(adar, 13);
+    if taanit_esther.weekday() == Weekday::Sat {
+        add("Ta'anit Esther", day(adar, 11));
+    } else {
+        add("Ta'anit Esther", taanit_esther);
+    }
+    add("Erev Purim", day(adar, 13));
+    add("Purim", day(adar, 14));
+    add("Shushan Purim", day(adar, 15));
+
+    //Nisan
+    add("Yom HaAliyah", day(NISAN, 10));
+    let taanit_bechorot = day(NISAN, 14);
+    if taanit_bechorot.weekday() == Weekday::Sat {
+        add("Ta'anit Bechorot", day(NISAN, 12));
+    } else {
+        add("Ta'anit Bechorot", taanit_bechorot);
+    }
+    add("Erev Pesach", day(NISAN, 14));
+    add("Pesach I", day(NISAN, 15));
+    for (i, numeral) in ["II", "III", "IV", "V", "VI"].iter().enumerate() {
+        add(
+            &format!("Pesach {} (CH''M)", numeral),
+            day(NISAN, 16 + i as u32),
+        );
+    }
+    add("Pesach VII", day(NISAN, 21));
+    let yom_hashoah = day(NISAN, 27);
+    add(
+        "Yom HaShoah",
+        match yom_hashoah.weekday() {
+            Weekday::Fri => day(NISAN, 26),
+            Weekday::Sun => day(NISAN, 28),
+            _ => yom_hashoah,
+        },
+    );
+
+    //Iyar - Sivan
+    let atzmaut = day(IYAR, 5);
+    let atzmaut = match atzmaut.weekday() {
+        Weekday::Fri => day(IYAR, 4),
+        Weekday::Sat => day(IYAR, 3),
+        Weekday::Mon => day(IYAR, 6),
+        _ => atzmaut,
+    };
+    add("Yom HaZikaron", atzmaut - Duration::days(1));
+    add("Yom HaAtzma'ut", atzmaut);
+    if year >= 5764 {
+        add("Herzl Day", postpone_from_shabbat(day(IYAR, 10)));
+    }
+    add("Pesach Sheni", day(IYAR, 14));
+    add("Lag BaOmer", day(IYAR, 18));
+    add("Yom Yerushalayim", day(IYAR, 28));
+    add("Erev Shavuot", day(SIVAN, 5));
+    add("Shavuot", day(SIVAN, 6));
+
+    //Tammuz - Elul
+    add("Tzom Tammuz", postpone_from_shabbat(day(TAMMUZ, 17)));
+    if year >= 5765 {
+        add("Jabotinsky Day", postpone_from_shabbat(day(TAMMUZ, 29)));
+    }
+    let tisha_bav = postpone_from_shabbat(day(AV, 9));
+    add("Erev Tish'a B'Av", tisha_bav - Duration::days(1));
+    add("Tish'a B'Av", tisha_bav);
+    add("Tu B'Av", day(AV, 15));
+    add("Rosh Hashana LaBehemot", day(ELUL, 1));
+    //Saturday night before Rosh Hashana, a week earlier if it falls less than 4 days before it.
+    let next_rosh_hashana = to_gregorian(year + 1, TISHREI, 1);
+    let selichot = (4..11)
+        .map(|d| next_rosh_hashana - Duration::days(d))
+        .find(|d| d.weekday() == Weekday::Sat)
+        .unwrap();
+    add("Leil Selichot", selichot);
+
+    list
+}
+
+//hebcal's subcategory of a holiday
+fn category(title: &str) -> &'static str {
+    match title {
+        "Tzom Gedaliah" | "Asara B'Tevet" | "Ta'anit Esther" | "Ta'anit Bechorot"
+        | "Tzom Tammuz" | "Erev Tish'a B'Av" | "Tish'a B'Av" => "fast",
+        "Sigd"
+        | "Yom HaAliyah"
+        | "Yom HaShoah"
+        | "Yom HaZikaron"
+        | "Yom HaAtzma'ut"
+        | "Yom Yerushalayim"
+        | "Yitzhak Rabin Memorial Day"
+        | "Ben-Gurion Day"
+        | "Hebrew Language Day"
+        | "Family Day"
+        | "Herzl Day"
+        | "Jabotinsky Day" => "modern",
+        "Tu BiShvat"
+        | "Chag HaBanot"
+        | "Purim Katan"
+        | "Shushan Purim"
+        | "Pesach Sheni"
+        | "Lag BaOmer"
+        | "Tu B'Av"
+        | "Rosh Hashana LaBehemot"
+        | "Leil Selichot" => "minor",
+        _ => "major",
+    }
+}
+
+//Fasts and memorial days that fall on Shabbat are pushed to Sunday.
+fn postpone_from_shabbat(date: NaiveDate) -> NaiveDate {
+    if date.weekday() == Weekday::Sat {
+        date + Duration::days(1)
+    } else {
+        date
+    }
+}
+
+fn to_gregorian(year: i64, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_num_days_from_ce_opt(fixed_from_hebrew(year, month, day) as i32)
+        .expect("Hebrew date out of range")
+}
+
+fn fixed_from_hebrew(year: i64, month: u32, day: u32) -> i64 {
+    let mut fixed = new_year(year) + day as i64 - 1;
+    if month < TISHREI {
+        fixed += (TISHREI..=last_month_of_year(year))
+            .chain(NISAN..month)
+            .map(|m| days_in_month(year, m))
+            .sum::<i64>();
+    } else {
+        fixed += (TISHREI..month)
+            .map(|m| days_in_month(year, m))
+            .sum::<i64>();
+    }
+    fixed
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (7 * year + 1).rem_euclid(19) < 7
+}
+
+fn last_month_of_year(year: i64) -> u32 {
+    if is_leap_year(year) {
+        ADAR_II
+    } else {
+        ADAR
+    }
+}
+
+fn days_in_month(year: i64, month: u32) -> i64 {
+    match month {
+        IYAR | TAMMUZ | ELUL | TEVET | ADAR_II => 29,
+        ADAR if !is_leap_year(year) => 29,
+        CHESHVAN if !is_long_cheshvan(year) => 29,
+        KISLEV if is_short_kislev(year) => 29,
+        _ => 30,
+    }
+}
+
+fn days_in_year(year: i64) -> i64 {
+    new_year(year + 1) - new_year(year)
+}
+
+//Complete years (355/385 days) have a 30 day Cheshvan
+fn is_long_cheshvan(year: i64) -> bool {
+    days_in_year(year) % 10 == 5
+}
+
+//Deficient years (353/383 days) have a 29 day Kislev
+fn is_short_kislev(year: i64) -> bool {
+    days_in_year(year) % 10 == 3
+}
+
+fn new_year(year: i64) -> i64 {
+    HEBREW_EPOCH + elapsed_days(year) + new_year_delay(year)
+}
+
+//Days from the epoch to the molad of Tishrei, including the "lo ADU" postponement.
+fn elapsed_days(year: i64) -> i64 {
+    let months_elapsed = (235 * year - 234).div_euclid(19);
+    let parts_elapsed = 12084 + 13753 * months_elapsed;
+    let days = 29 * months_elapsed + parts_elapsed.div_euclid(25920);
+    if (3 * (days + 1)).rem_euclid(7) < 3 {
+        days + 1
+    } else {
+        days
+    }
+}
+
+//Keeps every year at a valid length of 353-355 or 383-385 days.
+fn new_year_delay(year: i64) -> i64 {
+    let previous = elapsed_days(year - 1);
+    let current = elapsed_days(year);
+    let next = elapsed_days(year + 1);
+    if next - current == 356 {
+        2
+    } else if current - previous == 382 {
+        1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Items hebcal.com lists for year=2022 with maj, min and mod on (Israel schedule), transcribed
+    //without their other fields.
+    const HEBCAL_2022: &str = r#"{"items":[
+{"title":"Tu BiShvat","date":"2022-01-17"},
+{"title":"Family Day","date":"2022-02-01"},
+{"title":"Purim Katan","date":"2022-02-15"},
+{"title":"Ta'anit Esther","date":"2022-03-16"},
+{"title":"Erev Purim","date":"2022-03-16"},
+{"title":"Purim","date":"2022-03-17"},
+{"title":"Shushan Purim","date":"2022-03-18"},
+{"title":"Yom HaAliyah","date":"2022-04-11"},
+{"title":"Ta'anit Bechorot","date":"2022-04-15"},
+{"title":"Erev Pesach","date":"2022-04-15"},
+{"title":"Pesach I","date":"2022-04-16"},
+{"title":"Pesach II (CH''M)","date":"2022-04-17"},
+{"title":"Pesach III (CH''M)","date":"2022-04-18"},
+{"title":"Pesach IV (CH''M)","date":"2022-04-19"},
+{"title":"Pesach V (CH''M)","date":"2022-04-20"},
+{"title":"Pesach VI (CH''M)","date":"2022-04-21"},
+{"title":"Pesach VII","date":"2022-04-22"},
+{"title":"Yom HaShoah","date":"2022-04-28"},
+{"title":"Yom HaZikaron","date":"2022-05-04"},
+{"title":"Yom HaAtzma'ut","date":"2022-05-05"},
+{"title":"Herzl Day","date":"2022-05-11"},
+{"title":"Pesach Sheni","date":"2022-05-15"},
+{"title":"Lag BaOmer","date":"2022-05-19"},
+{"title":"Yom Yerushalayim","date":"2022-05-29"},
+{"title":"Erev Shavuot","date":"2022-06-04"},
+{"title":"Shavuot","date":"2022-06-05"},
+{"title":"Tzom Tammuz","date":"2022-07-17"},
+{"title":"Jabotinsky Day","date":"2022-07-28"},
+{"title":"Erev Tish'a B'Av","date":"2022-08-06"},
+{"title":"Tish'a B'Av","date":"2022-08-07"},
+{"title":"Tu B'Av","date":"2022-08-12"},
+{"title":"Rosh Hashana LaBehemot","date":"2022-08-28"},
+{"title":"Leil Selichot","date":"2022-09-17"},
+{"title":"Erev Rosh Hashana","date":"2022-09-25"},
+{"title":"Rosh Hashana 5783","date":"2022-09-26"},
+{"title":"Rosh Hashana II","date":"2022-09-27"},
+{"title":"Tzom Gedaliah","date":"2022-09-28"},
+{"title":"Erev Yom Kippur","date":"2022-10-04"},
+{"title":"Yom Kippur","date":"2022-10-05"},
+{"title":"Erev Sukkot","date":"2022-10-09"},
+{"title":"Sukkot I","date":"2022-10-10"},
+{"title":"Sukkot II (CH''M)","date":"2022-10-11"},
+{"title":"Sukkot III (CH''M)","date":"2022-10-12"},
+{"title":"Sukkot IV (CH''M)","date":"2022-10-13"},
+{"title":"Sukkot V (CH''M)","date":"2022-10-14"},
+{"title":"Sukkot VI (CH''M)","date":"2022-10-15"},
+{"title":"Sukkot VII (Hoshana Raba)","date":"2022-10-16"},
+{"title":"Shmini Atzeret","date":"2022-10-17"},
+{"title":"Yitzhak Rabin Memorial Day","date":"2022-11-06"},
+{"title":"Sigd","date":"2022-11-23"},
+{"title":"Ben-Gurion Day","date":"2022-11-30"},
+{"title":"Chanukah: 1 Candle","date":"2022-12-18"},
+{"title":"Chanukah: 2 Candles","date":"2022-12-19"},
+{"title":"Chanukah: 3 Candles","date":"2022-12-20"},
+{"title":"Chanukah: 4 Candles","date":"2022-12-21"},
+{"title":"Chanukah: 5 Candles","date":"2022-12-22"},
+{"title":"Chanukah: 6 Candles","date":"2022-12-23"},
+{"title":"Chanukah: 7 Candles","date":"2022-12-24"},
+{"title":"Chanukah: 8 Candles","date":"2022-12-25"},
+{"title":"Chag HaBanot","date":"2022-12-25"},
+{"title":"Chanukah: 8th Day","date":"2022-12-26"}
+]}"#;
+
+    #[test]
+    fn matches_hebcal_year() {
+        let sorted = |holidays: Vec<HebDate>| {
+            let mut holidays: Vec<(NaiveDate, String)> =
+                holidays.into_iter().map(|h| (h.date, h.title)).collect();
+            holidays.sort();
+            holidays
+        };
+        assert_eq!(
+            sorted(holidays_for_year(2022)),
+            sorted(super::super::get_struct(HEBCAL_2022).unwrap())
+        );
+    }
+
+    #[test]
+    fn modern_days_moved_off_weekends() {
+        let find = |year: i32, title: &str| {
+            holidays_for_year(year)
+                .into_iter()
+                .find(|h| h.title == title)
+                .unwrap()
+                .date
+        };
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        //12 Cheshvan 5784 is a Friday, the memorial day is on Thursday
+        assert_eq!(find(2023, "Yitzhak Rabin Memorial Day"), date(2023, 10, 26));
+        //6 Kislev 5785 is a Saturday
+        assert_eq!(find(2024, "Ben-Gurion Day"), date(2024, 12, 8));
+        //10 Iyar and 29 Tammuz of the leap year 5784 are Saturdays
+        assert_eq!(find(2024, "Herzl Day"), date(2024, 5, 19));
+        assert_eq!(find(2024, "Jabotinsky Day"), date(2024, 8, 4));
+        assert_eq!(find(2024, "Family Day"), date(2024, 2, 9));
+        assert_eq!(find(2023, "Hebrew Language Day"), date(2023, 1, 15));
+        assert_eq!(find(2023, "Chag HaBanot"), date(2023, 12, 13));
+    }
+
+    #[test]
+    fn postponed_years() {
+        let find = |year: i32, title: &str| {
+            holidays_for_year(year)
+                .into_iter()
+                .find(|h| h.title == title)
+                .unwrap()
+                .date
+        };
+        assert_eq!(
+            find(2023, "Rosh Hashana 5784"),
+            NaiveDate::from_ymd_opt(2023, 9, 16).unwrap()
+        );
+        assert_eq!(
+            find(2024, "Pesach I"),
+            NaiveDate::from_ymd_opt(2024, 4, 23).unwrap()
+        );
+        assert_eq!(
+            find(2024, "Chanukah: 1 Candle"),
+            NaiveDate::from_ymd_opt(2024, 12, 25).unwrap()
+        );
+        //Chanukah that started the previous December
+        assert_eq!(
+            find(2025, "Chanukah: 8th Day"),
+            NaiveDate::from_ymd_opt(2025, 1, 2).unwrap()
+        );
+        //10 Tevet of both 5783 and 5784 fall on 2023
+        let asara_btevet: Vec<NaiveDate> = holidays_for_year(2023)
+            .into_iter()
+            .filter(|h| h.title == "Asara B'Tevet")
+            .map(|h| h.date)
+            .collect();
+        assert_eq!(
+            asara_btevet,
+            vec![
+                NaiveDate::from_ymd_opt(2023, 1, 3).unwrap(),
+                NaiveDate::from_ymd_opt(2023, 12, 22).unwrap()
+            ]
+        );
+    }
+}
@@ -1,65 +1,62 @@
+mod cache;
+mod exclusion;
+mod hebrew;
+mod policy;
+mod provider;
+
 use std::ops::Deref;
 
 use chrono::NaiveDate;
 
-use csv::{self, Reader};
-use reqwest;
 use serde::{Deserialize, Serialize};
 use serde_json::{self, Value};
-use table_configs::paths;
-
-fn get_heb_cal() -> Result<String, reqwest::Error> {
-    let url = "https://www.hebcal.com/hebcal?v=1&cfg=json&year=now&month=x&maj=on&min=on&mod=on&i=on&geo=none&c=off";
-    let str = reqwest::get(url)?.text()?;
-    Ok(str)
-}
+use table_configs::config::HolidaySource;
 
-pub fn generate_heb() -> Result<Vec<HebDate>, Box<dyn std::error::Error>> {
-    let heb_cal = get_heb_cal().expect("Could not get Hebrew Holidays from hebcal.com");
-    std::fs::write(paths::get_hebdate_path(), &heb_cal)?;
+pub use cache::{CachePolicy, HolidayCache};
+pub use exclusion::{exclude_holidays_from_file, ExclusionReport};
+pub use policy::apply_policy;
+pub use provider::{
+    ChainProvider, CsvProvider, FileProvider, HebcalProvider, HolidayProvider, IcsProvider,
+    OfflineProvider,
+};
 
-    Ok(get_struct(&heb_cal)?)
+///Collect the holidays of every configured source between `from` and `to`.
+pub fn generate_heb(
+    sources: &[HolidaySource],
+    policy: &CachePolicy,
+    from: &NaiveDate,
+    to: &NaiveDate,
+) -> Result<Vec<HebDate>, Box<dyn std::error::Error>> {
+    ChainProvider::from_sources(sources, policy).holidays(from, to)
 }
 
 fn get_struct(json: &str) -> Result<Vec<HebDate>, Box<dyn std::error::Error>> {
-    let data = r#json;
-    let wrapper: Value = serde_json::from_str(&data)?;
-    let wrapper = wrapper["items"].clone();
+    let wrapper: Value = serde_json::from_str(json)?;
+    let array = wrapper["items"]
+        .as_array()
+        .ok_or("the reply has no list of items")?;
     let mut items = Vec::<HebDate>::new();
-    let array = wrapper.as_array().unwrap();
-    let val_to_str = |x: &Value| x.as_str().unwrap().to_string();
     for item in array {
-        let a = item.as_object().unwrap();
+        let a = item.as_object().ok_or("an item is not an object")?;
+        let field = |name| a.get(name).and_then(|x: &Value| x.as_str());
+        let date = field("date").ok_or("an item has no date")?;
         items.push(HebDate {
-            date: NaiveDate::parse_from_str(a["date"].as_str().unwrap(), "%Y-%m-%d")?,
-            title: val_to_str(&a["title"]),
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d")?,
+            title: field("title").ok_or("an item has no title")?.to_string(),
+            category: field("subcat").map(String::from),
         });
     }
     Ok(items)
 }
 
-pub fn exclude_holidays_from_file(
-    dates: Vec<HebDate>,
-    file: &str,
-) -> Result<Vec<HebDate>, Box<dyn std::error::Error>> {
-    let file = std::fs::read_to_string(file)?;
-    let mut rdr = Reader::from_reader(file.as_bytes());
-    let iter = rdr
-        .deserialize()
-        .map(|x: Result<ExcludedName, csv::Error>| x.unwrap());
-
-    let mut filtered = dates;
-    for row in iter {
-        filtered.retain(|f| !f.title.contains(&row.names));
-    }
+pub use hebrew::holidays_for_year;
 
-    Ok(filtered)
-}
-
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct HebDate {
     pub title: String,
     pub date: NaiveDate,
+    ///hebcal's subcategory, i.e. "major", "minor", "modern" or "fast"
+    pub category: Option<String>,
 }
 impl HebDate {
     pub fn from(raw: &HebDateRaw) -> Self {
@@ -67,6 +64,7 @@ impl HebDate {
             title: raw.title.clone(),
             date: NaiveDate::parse_from_str(&raw.date, "%Y-%m-%d")
                 .expect("Failed to read Date from heb_dates file"),
+            category: raw.category.clone(),
         }
     }
 }
@@ -82,19 +80,18 @@ impl Deref for HebDate {
 pub struct HebDateRaw {
     pub title: String,
     pub date: String,
+    #[serde(default)]
+    pub category: Option<String>,
 }
 impl HebDateRaw {
     pub fn from(heb_date: &HebDate) -> Self {
         Self {
             title: heb_date.title.clone(),
             date: format!("{}", heb_date.date.format("%Y-%m-%d")),
+            category: heb_date.category.clone(),
         }
     }
 }
-#[derive(Deserialize)]
-struct ExcludedName {
-    pub names: String,
-}
 // #[cfg(test)]
 // mod tests {
 //     use chrono::NaiveDate;
@@ -0,0 +1,137 @@
+use chrono::NaiveDate;
+use table_configs::config::{DayPolicy, HolidayPolicy};
+
+use super::HebDate;
+
+///Apply the eve, Chol HaMoed and span policies. Returns the holidays that stay blocked and the
+///dates that become short days.
+pub fn apply_policy(
+    holidays: Vec<HebDate>,
+    policy: &HolidayPolicy,
+) -> (Vec<HebDate>, Vec<NaiveDate>) {
+    let spans = if policy.span {
+        get_spans(&holidays)
+    } else {
+        vec![]
+    };
+
+    let mut blocked = Vec::new();
+    let mut short_days = Vec::new();
+    for holiday in holidays {
+        let day_policy = if is_erev(&holiday) {
+            policy.erev
+        } else if is_chol_hamoed(&holiday) {
+            policy.chol_hamoed
+        } else {
+            DayPolicy::Block
+        };
+        match day_policy {
+            DayPolicy::Block => blocked.push(holiday),
+            DayPolicy::Short => short_days.push(holiday.date),
+            DayPolicy::Work => {}
+        }
+    }
+
+    //A spanned day is blocked even when the policy of its own holiday isn't
+    for (title, first, last) in spans {
+        for date in first.iter_days().take_while(|d| *d <= last) {
+            short_days.retain(|d| *d != date);
+            if !blocked.iter().any(|h| h.date == date) {
+                blocked.push(HebDate {
+                    title: title.to_string(),
+                    date,
+                    category: None,
+                });
+            }
+        }
+    }
+    blocked.sort_by_key(|h| h.date);
+    short_days.sort();
+    short_days.dedup();
+    (blocked, short_days)
+}
+
+fn is_erev(holiday: &HebDate) -> bool {
+    holiday.title.starts_with("Erev ")
+}
+
+fn is_chol_hamoed(holiday: &HebDate) -> bool {
+    holiday.title.contains("(CH''M)") || holiday.title.contains("Hoshana Raba")
+}
+
+//Name of the multi-day holiday a day belongs to
+fn span_group(holiday: &HebDate) -> Option<&'static str> {
+    let title = holiday.title.as_str();
+    if title.starts_with("Pesach ") && title != "Pesach Sheni" {
+        Some("Pesach")
+    } else if title.starts_with("Sukkot ") || title == "Shmini Atzeret" {
+        Some("Sukkot")
+    } else if title.starts_with("Chanukah") {
+        Some("Chanukah")
+    } else {
+        None
+    }
+}
+
+//First and last day of every occurrence of a multi-day holiday
+fn get_spans(holidays: &[HebDate]) -> Vec<(&'static str, NaiveDate, NaiveDate)> {
+    let mut days: Vec<(&'static str, NaiveDate)> = holidays
+        .iter()
+        .filter_map(|h| span_group(h).map(|g| (g, h.date)))
+        .collect();
+    days.sort();
+    let mut spans: Vec<(&'static str, NaiveDate, NaiveDate)> = Vec::new();
+    for (group, date) in days {
+        match spans.last_mut() {
+            //Days of the same holiday are never more than a week apart
+            Some(span) if span.0 == group && (date - span.2).num_days() <= 7 => span.2 = date,
+            _ => spans.push((group, date, date)),
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heb_cal::holidays_for_year;
+
+    fn date(m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2022, m, d).unwrap()
+    }
+
+    #[test]
+    fn short_chol_hamoed_and_working_eve() {
+        let policy = HolidayPolicy {
+            erev: DayPolicy::Work,
+            chol_hamoed: DayPolicy::Short,
+            span: false,
+        };
+        let (blocked, short_days) = apply_policy(holidays_for_year(2022), &policy);
+        assert!(!blocked.iter().any(|h| h.title == "Erev Pesach"));
+        assert!(blocked.iter().any(|h| h.title == "Pesach I"));
+        assert!(!blocked.iter().any(|h| h.date == date(4, 18)));
+        assert!(short_days.contains(&date(4, 18)));
+        //Hoshana Raba
+        assert!(short_days.contains(&date(10, 16)));
+    }
+
+    #[test]
+    fn span_fills_excluded_days() {
+        let policy = HolidayPolicy {
+            erev: DayPolicy::Block,
+            chol_hamoed: DayPolicy::Work,
+            span: true,
+        };
+        let mut holidays = holidays_for_year(2022);
+        holidays.retain(|h| h.title != "Chanukah: 4 Candles");
+        let (blocked, short_days) = apply_policy(holidays, &policy);
+        assert!(short_days.is_empty());
+        for day in 16..=22 {
+            assert!(blocked.iter().any(|h| h.date == date(4, day)));
+        }
+        assert!(blocked.iter().any(|h| h.date == date(12, 21)));
+        assert!(blocked.iter().any(|h| h.title == "Erev Pesach"));
+        assert!(!blocked.iter().any(|h| h.date == date(5, 1)));
+    }
+}
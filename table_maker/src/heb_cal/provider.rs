@@ -0,0 +1,441 @@
+use std::error::Error;
+
+use chrono::{Datelike, NaiveDate};
+use csv::Reader;
+use serde_json::Value;
+use table_configs::config::HolidaySource;
+
+use super::cache::{CachePolicy, HolidayCache};
+use super::{get_struct, hebrew, HebDate, HebDateRaw};
+
+///Source of dates on which no one should be scheduled.
+pub trait HolidayProvider {
+    ///Name used when reporting problems with the source.
+    fn name(&self) -> String;
+    ///Holidays between `from` and `to`, both inclusive.
+    fn holidays(&self, from: &NaiveDate, to: &NaiveDate) -> Result<Vec<HebDate>, Box<dyn Error>>;
+}
+
+pub fn from_source(source: &HolidaySource, policy: &CachePolicy) -> Box<dyn HolidayProvider> {
+    match source {
+        HolidaySource::Hebcal { base_url } => Box::new(HebcalProvider {
+            base_url: base_url.clone(),
+            policy: policy.clone(),
+        }),
+        HolidaySource::Offline => Box::new(OfflineProvider),
+        HolidaySource::File { path } => Box::new(FileProvider { path: path.clone() }),
+        HolidaySource::Csv { path } => Box::new(CsvProvider { path: path.clone() }),
+        HolidaySource::Ics { path } => Box::new(IcsProvider { path: path.clone() }),
+    }
+}
+
+//Keep only the dates inside the requested range
+fn in_range(dates: Vec<HebDate>, from: &NaiveDate, to: &NaiveDate) -> Vec<HebDate> {
+    dates
+        .into_iter()
+        .filter(|d| d.date >= *from && d.date <= *to)
+        .collect()
+}
+
+///hebcal.com, or any server answering the same API. Every fetched year is kept in the cache.
+///When a fetch fails the cached copy is used, and without one the holidays are computed offline.
+pub struct HebcalProvider {
+    pub base_url: String,
+    pub policy: CachePolicy,
+}
+impl HebcalProvider {
+    fn get_heb_cal(&self, year: i32) -> Result<String, Box<dyn Error>> {
+        let url = format!(
+            "{}/hebcal?v=1&cfg=json&year={}&month=x&maj=on&min=on&mod=on&i=on&geo=none&c=off",
+            self.base_url.trim_end_matches('/'),
+            year
+        );
+        let mut response = reqwest::get(&url)?;
+        if !response.status().is_success() {
+            return Err(format!("{} replied {}", self.name(), response.status()).into());
+        }
+        Ok(response.text()?)
+    }
+
+    fn fetch(&self, year: i32) -> Result<Vec<HebDate>, Box<dyn Error>> {
+        get_struct(&self.get_heb_cal(year)?)
+    }
+}
+impl HolidayProvider for HebcalProvider {
+    fn name(&self) -> String {
+        format!("hebcal ({})", self.base_url)
+    }
+
+    fn holidays(&self, from: &NaiveDate, to: &NaiveDate) -> Result<Vec<HebDate>, Box<dyn Error>> {
+        let today = chrono::Local::now().date_naive();
+        let mut cache = HolidayCache::load(&self.policy.path);
+        let mut items = Vec::new();
+        for year in from.year()..=to.year() {
+            let cached = cache.get(&self.name(), year);
+            if let Some((fetched, cached)) = &cached {
+                if self.policy.is_fresh(fetched, &today) {
+                    items.extend(cached.iter().cloned());
+                    continue;
+                }
+            }
+            match self.fetch(year) {
+                Ok(mut fetched) => {
+                    cache.insert(&self.name(), year, today, &fetched);
+                    items.append(&mut fetched);
+                }
+                Err(e) => match cached {
+                    Some((fetched, mut cached)) => {
+                        eprintln!(
+                            "Warning: could not get Hebrew Holidays for {} from {} ({}). Using the copy cached on {}.",
+                            year,
+                            self.name(),
+                            e,
+                            fetched
+                        );
+                        items.append(&mut cached);
+                    }
+                    None => {
+                        eprintln!(
+                            "Warning: could not get Hebrew Holidays for {} from {} ({}). Computing them offline.",
+                            year,
+                            self.name(),
+                            e
+                        );
+                        items.append(&mut hebrew::holidays_for_year(year));
+                    }
+                },
+            }
+        }
+        cache.save(&self.policy.path)?;
+        Ok(in_range(items, from, to))
+    }
+}
+
+///Holidays computed from the Hebrew calendar rules, without network access.
+pub struct OfflineProvider;
+impl HolidayProvider for OfflineProvider {
+    fn name(&self) -> String {
+        "offline".to_string()
+    }
+
+    fn holidays(&self, from: &NaiveDate, to: &NaiveDate) -> Result<Vec<HebDate>, Box<dyn Error>> {
+        let items = (from.year()..=to.year())
+            .flat_map(hebrew::holidays_for_year)
+            .collect();
+        Ok(in_range(items, from, to))
+    }
+}
+
+///Holidays cached in heb_date.json by earlier fetches, from any source. A json file in
+///hebcal's own format is read as well.
+pub struct FileProvider {
+    pub path: String,
+}
+impl HolidayProvider for FileProvider {
+    fn name(&self) -> String {
+        format!("file ({})", self.path)
+    }
+
+    fn holidays(&self, from: &NaiveDate, to: &NaiveDate) -> Result<Vec<HebDate>, Box<dyn Error>> {
+        let json = std::fs::read_to_string(&self.path)?;
+        let value: Value = serde_json::from_str(&json)?;
+        let items = if value.get("items").is_some() {
+            get_struct(&json)?
+        } else {
+            let cache = HolidayCache::load(&self.path);
+            (from.year()..=to.year())
+                .flat_map(|year| cache.get_any(year))
+                .collect()
+        };
+        Ok(in_range(items, from, to))
+    }
+}
+
+///User maintained csv of extra closure dates with `title,date` columns.
+pub struct CsvProvider {
+    pub path: String,
+}
+impl HolidayProvider for CsvProvider {
+    fn name(&self) -> String {
+        format!("csv ({})", self.path)
+    }
+
+    fn holidays(&self, from: &NaiveDate, to: &NaiveDate) -> Result<Vec<HebDate>, Box<dyn Error>> {
+        let file = std::fs::read_to_string(&self.path)?;
+        let mut rdr = Reader::from_reader(file.as_bytes());
+        let mut items = Vec::new();
+        for row in rdr.deserialize() {
+            let row: HebDateRaw = row?;
+            items.push(HebDate {
+                title: row.title,
+                date: NaiveDate::parse_from_str(row.date.trim(), "%Y-%m-%d")?,
+                category: row.category,
+            });
+        }
+        Ok(in_range(items, from, to))
+    }
+}
+
+///Events of an imported .ics calendar. Every day an all-day event spans is a holiday.
+pub struct IcsProvider {
+    pub path: String,
+}
+impl HolidayProvider for IcsProvider {
+    fn name(&self) -> String {
+        format!("ics ({})", self.path)
+    }
+
+    fn holidays(&self, from: &NaiveDate, to: &NaiveDate) -> Result<Vec<HebDate>, Box<dyn Error>> {
+        let file = std::fs::read_to_string(&self.path)?;
+        Ok(in_range(parse_ics(&file)?, from, to))
+    }
+}
+
+fn parse_ics(ics: &str) -> Result<Vec<HebDate>, Box<dyn Error>> {
+    //Unfold lines continued with a leading space or tab
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (
+            line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')),
+            lines.last_mut(),
+        ) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut items = Vec::new();
+    let mut title = String::new();
+    let mut start: Option<NaiveDate> = None;
+    let mut end: Option<NaiveDate> = None;
+    let mut all_day = false;
+    for line in lines {
+        let (name, value) = match line.split_once(':') {
+            Some(x) => x,
+            None => continue,
+        };
+        let mut params = name.split(';');
+        match params.next().unwrap_or("") {
+            "BEGIN" if value == "VEVENT" => {
+                title.clear();
+                start = None;
+                end = None;
+                all_day = false;
+            }
+            "SUMMARY" => {
+                title = value
+                    .replace("\\n", " ")
+                    .replace("\\,", ",")
+                    .replace("\\;", ";")
+                    .replace("\\\\", "\\")
+            }
+            "DTSTART" => {
+                all_day = params.any(|p| p == "VALUE=DATE") || value.len() == 8;
+                start = Some(NaiveDate::parse_from_str(
+                    value.get(..8).unwrap_or(value),
+                    "%Y%m%d",
+                )?);
+            }
+            "DTEND" => {
+                end = Some(NaiveDate::parse_from_str(
+                    value.get(..8).unwrap_or(value),
+                    "%Y%m%d",
+                )?)
+            }
+            "END" if value == "VEVENT" => {
+                let start = match start {
+                    Some(start) => start,
+                    None => continue,
+                };
+                //DTEND of an all-day event is the day after it ends
+                let days = match end {
+                    Some(end) if all_day && end > start => (end - start).num_days(),
+                    _ => 1,
+                };
+                for date in start.iter_days().take(days as usize) {
+                    items.push(HebDate {
+                        title: title.clone(),
+                        date,
+                        category: None,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(items)
+}
+
+///Combines several sources into a single list without duplicates. A source that fails is skipped
+///with a warning, only when every source fails there are no holidays to return.
+pub struct ChainProvider {
+    providers: Vec<Box<dyn HolidayProvider>>,
+}
+impl ChainProvider {
+    pub fn new(providers: Vec<Box<dyn HolidayProvider>>) -> Self {
+        Self { providers }
+    }
+
+    pub fn from_sources(sources: &[HolidaySource], policy: &CachePolicy) -> Self {
+        Self::new(sources.iter().map(|s| from_source(s, policy)).collect())
+    }
+}
+impl HolidayProvider for ChainProvider {
+    fn name(&self) -> String {
+        self.providers
+            .iter()
+            .map(|p| p.name())
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    fn holidays(&self, from: &NaiveDate, to: &NaiveDate) -> Result<Vec<HebDate>, Box<dyn Error>> {
+        let mut items: Vec<HebDate> = Vec::new();
+        let mut errors = Vec::new();
+        for provider in &self.providers {
+            let found = match provider.holidays(from, to) {
+                Ok(found) => found,
+                Err(e) => {
+                    let error = format!("Could not get holidays from {}: {}", provider.name(), e);
+                    eprintln!("Warning: {}. Skipping it.", error);
+                    errors.push(error);
+                    continue;
+                }
+            };
+            for holiday in found {
+                if !items
+                    .iter()
+                    .any(|h| h.date == holiday.date && h.title == holiday.title)
+                {
+                    items.push(holiday);
+                }
+            }
+        }
+        if !self.providers.is_empty() && errors.len() == self.providers.len() {
+            return Err(errors.join("\n").into());
+        }
+        items.sort_by_key(|h| h.date);
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fake_server::serve;
+
+    struct Failing;
+    impl HolidayProvider for Failing {
+        fn name(&self) -> String {
+            "failing".to_string()
+        }
+        fn holidays(&self, _: &NaiveDate, _: &NaiveDate) -> Result<Vec<HebDate>, Box<dyn Error>> {
+            Err("unreadable".into())
+        }
+    }
+
+    struct Fixed(Vec<HebDate>);
+    impl HolidayProvider for Fixed {
+        fn name(&self) -> String {
+            "fixed".to_string()
+        }
+        fn holidays(
+            &self,
+            from: &NaiveDate,
+            to: &NaiveDate,
+        ) -> Result<Vec<HebDate>, Box<dyn Error>> {
+            let items = self.0.to_vec();
+            Ok(in_range(items, from, to))
+        }
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn ics_events() {
+        let ics = "BEGIN:VCALENDAR\r
+BEGIN:VEVENT\r
+DTSTART;VALUE=DATE:20220801\r
+DTEND;VALUE=DATE:20220804\r
+SUMMARY:Office\\, closed for\r
+  renovation\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+DTSTART:20220810T090000Z\r
+DTEND:20220810T100000Z\r
+SUMMARY:Team day\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+        let items = parse_ics(ics).unwrap();
+        assert_eq!(items.len(), 4);
+        assert_eq!(items[0].title, "Office, closed for renovation");
+        assert_eq!(items[2].date, date(2022, 8, 3));
+        assert_eq!(items[3].title, "Team day");
+        assert_eq!(items[3].date, date(2022, 8, 10));
+    }
+
+    #[test]
+    fn chain_merges_sources() {
+        let closure = || HebDate {
+            title: "Closure".to_string(),
+            date: date(2022, 6, 1),
+            category: None,
+        };
+        let chain = ChainProvider::new(vec![
+            Box::new(OfflineProvider),
+            Box::new(Fixed(vec![closure()])),
+            Box::new(Fixed(vec![closure()])),
+        ]);
+        let items = chain
+            .holidays(&date(2022, 4, 1), &date(2022, 6, 30))
+            .unwrap();
+        assert_eq!(items.iter().filter(|h| h.title == "Closure").count(), 1);
+        assert!(items.iter().any(|h| h.title == "Pesach I"));
+        assert!(!items.iter().any(|h| h.title == "Purim"));
+    }
+
+    #[test]
+    fn chain_skips_failed_sources() {
+        let closure = HebDate {
+            title: "Closure".to_string(),
+            date: date(2022, 6, 1),
+            category: None,
+        };
+        let chain = ChainProvider::new(vec![Box::new(Failing), Box::new(Fixed(vec![closure]))]);
+        let items = chain
+            .holidays(&date(2022, 4, 1), &date(2022, 6, 30))
+            .unwrap();
+        assert_eq!(items.len(), 1);
+
+        let chain = ChainProvider::new(vec![Box::new(Failing), Box::new(Failing)]);
+        assert!(chain
+            .holidays(&date(2022, 4, 1), &date(2022, 6, 30))
+            .is_err());
+    }
+
+    #[test]
+    fn hebcal_base_url() {
+        let json = r#"{"items":[{"title":"Purim","date":"2022-03-17","subcat":"major"}]}"#;
+        let (base_url, handle) = serve(vec![("200 OK", json)]);
+        let cache = "./test_hebcal_base_url.json";
+        let provider = HebcalProvider {
+            base_url: format!("{}/", base_url),
+            policy: CachePolicy {
+                path: cache.to_string(),
+                max_age_days: 30,
+                refresh: false,
+            },
+        };
+        let items = provider.holidays(&date(2022, 1, 1), &date(2022, 12, 31));
+        std::fs::remove_file(cache).unwrap();
+        let items = items.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, "Purim");
+        assert_eq!(items[0].category.as_deref(), Some("major"));
+        let requests = handle.join().unwrap();
+        assert!(requests[0].starts_with("GET /hebcal?v=1&cfg=json&year=2022&"));
+    }
+}
@@ -1,11 +1,24 @@
+mod calendar;
 mod heb_cal;
 mod list;
 pub mod table_construction;
 
-pub use heb_cal::{HebDate, HebDateRaw};
-pub use list::Person;
-pub use table_construction::construction::{self, NamesTableRaw};
+pub use calendar::WorkCalendar;
+pub use heb_cal::{
+    holidays_for_year, CachePolicy, ChainProvider, CsvProvider, ExclusionReport, FileProvider,
+    HebDate, HebDateRaw, HebcalProvider, HolidayCache, HolidayProvider, IcsProvider,
+    OfflineProvider,
+};
+pub use list::{parse_candidates_from_file, Availability, Channel, Pairing, Person};
+pub use table_construction::construction::{
+    self, find_swaps, regenerate_schedule, regenerate_table, NamesTableRaw, Schedule,
+    ScheduleInputs, TableMode,
+};
 
-pub fn create_table(exclude_dates: bool) -> Result<String, Box<dyn std::error::Error>> {
-    construction::create_table(exclude_dates)
+pub fn create_table(
+    exclude_dates: bool,
+    refresh_holidays: bool,
+    mode: TableMode,
+) -> Result<String, Box<dyn std::error::Error>> {
+    construction::create_table(exclude_dates, refresh_holidays, mode)
 }
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use super::parse_weekdays;
+use chrono::{Datelike, NaiveDate, Weekday};
+use csv::Reader;
+use serde::Deserialize;
+
+//Row of unavailability.csv. A row without dates is always in effect and a row without weekdays
+//covers every day of its range.
+#[derive(Deserialize)]
+struct UnavailabilityRaw {
+    name: String,
+    #[serde(default)]
+    from: String,
+    #[serde(default)]
+    to: String,
+    #[serde(default)]
+    weekdays: String,
+}
+
+#[derive(Debug, Clone)]
+struct Window {
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    weekdays: Vec<Weekday>,
+}
+
+impl Window {
+    fn from(row: &UnavailabilityRaw) -> Result<Self, Box<dyn std::error::Error>> {
+        let parse_date = |date: &str| -> Result<Option<NaiveDate>, Box<dyn std::error::Error>> {
+            match date.trim() {
+                "" => Ok(None),
+                date => Ok(Some(NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(
+                    |_| {
+                        format!(
+                            "Wrong date \"{}\" for {} in unavailability file. Expecting YYYY-MM-DD.",
+                            date, row.name
+                        )
+                    },
+                )?)),
+            }
+        };
+        let weekdays = parse_weekdays(&row.weekdays).map_err(|day| {
+            format!(
+                "Wrong weekday \"{}\" for {} in unavailability file. Expecting 1 (Sunday) to 7 (Saturday).",
+                day, row.name
+            )
+        })?;
+        Ok(Self {
+            from: parse_date(&row.from)?,
+            to: parse_date(&row.to)?,
+            weekdays,
+        })
+    }
+
+    fn covers(&self, date: &NaiveDate) -> bool {
+        self.from.is_none_or(|from| from <= *date)
+            && self.to.is_none_or(|to| *date <= to)
+            && (self.weekdays.is_empty() || self.weekdays.contains(&date.weekday()))
+    }
+}
+
+///Dates on which people can't be scheduled, e.g. vacations, reserve duty or a weekly day off.
+#[derive(Debug, Clone, Default)]
+pub struct Availability {
+    windows: HashMap<String, Vec<Window>>,
+}
+
+impl Availability {
+    pub fn parse(csv: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut rdr = Reader::from_reader(csv.as_bytes());
+        let mut windows: HashMap<String, Vec<Window>> = HashMap::new();
+        for row in rdr.deserialize() {
+            let row: UnavailabilityRaw = row?;
+            windows
+                .entry(row.name.trim().to_string())
+                .or_default()
+                .push(Window::from(&row)?);
+        }
+        Ok(Self { windows })
+    }
+
+    ///A missing file means everybody is always available.
+    pub fn from_file(file: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if !std::path::Path::new(file).exists() {
+            return Ok(Self::default());
+        }
+        Self::parse(&std::fs::read_to_string(file)?)
+    }
+
+    pub fn is_available(&self, name: &str, date: &NaiveDate) -> bool {
+        self.windows
+            .get(name)
+            .is_none_or(|windows| !windows.iter().any(|w| w.covers(date)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows() {
+        let availability = Availability::parse(
+            "name,from,to,weekdays
+Joe,2022-05-01,2022-05-10,
+Joe,,,6
+Ann,2022-06-01,2022-06-30,2 3
+",
+        )
+        .unwrap();
+        let date = |m, d| NaiveDate::from_ymd_opt(2022, m, d).unwrap();
+        assert!(!availability.is_available("Joe", &date(5, 1)));
+        assert!(!availability.is_available("Joe", &date(5, 10)));
+        assert!(availability.is_available("Joe", &date(5, 11)));
+        //Fridays
+        assert!(!availability.is_available("Joe", &date(5, 13)));
+        assert!(!availability.is_available("Joe", &date(12, 30)));
+        //Mondays and Tuesdays of June
+        assert!(!availability.is_available("Ann", &date(6, 6)));
+        assert!(availability.is_available("Ann", &date(6, 8)));
+        assert!(availability.is_available("Ann", &date(7, 4)));
+        assert!(availability.is_available("Kaladin", &date(5, 1)));
+
+        assert!(Availability::parse("name,from,to,weekdays\nJoe,,,8\n").is_err());
+    }
+}
@@ -1,13 +1,27 @@
+mod availability;
+mod pairing;
 mod parse_people;
+mod scheduler;
+mod solver;
+pub use self::availability::Availability;
+pub use self::pairing::Pairing;
 pub use self::parse_people::*;
+use self::scheduler::unsatisfied;
+pub use self::scheduler::{scheduler_for, RoundRobin, Scheduler};
+pub use self::solver::Solver;
+use crate::calendar::WorkCalendar;
 use crate::heb_cal::HebDate;
 use chrono::{Datelike, NaiveDate, Weekday};
 use serde::{ser::SerializeStruct, Serialize};
+use std::collections::HashMap;
+use table_configs::config::{int_to_weekday, Frequency, Staffing};
 
 #[derive(Debug)]
 pub struct Row {
     pub person: Person,
     pub date: NaiveDate,
+    pub short_day: bool,
+    pub task: String,
 }
 
 impl Serialize for Row {
@@ -15,55 +29,307 @@ impl Serialize for Row {
     where
         S: serde::Serializer,
     {
-        let mut state = serializer.serialize_struct("Row", 2)?;
+        let mut state = serializer.serialize_struct("Row", 4)?;
         state.serialize_field("person", &serde_json::to_value(&self.person).unwrap())?;
         state.serialize_field("date", &self.date.to_string())?;
+        state.serialize_field("short_day", &self.short_day)?;
+        state.serialize_field("task", &self.task)?;
         state.end()
     }
 }
 
-pub fn get_dates(
-    people: &Vec<Person>,
-    holidays: &Vec<HebDate>,
+///Everything limiting who is assigned to which date.
+#[derive(Debug, Clone, Default)]
+pub struct Constraints {
+    pub staffing: Staffing,
+    pub frequency: Frequency,
+    pub availability: Availability,
+    ///Duties each person did in earlier tables. New dates go to whoever has the fewest in total,
+    ///relative to their weight.
+    pub history: HashMap<String, usize>,
+    ///Among the people whose turn it is, prefer whoever had the fewest duties on the date's weekday
+    pub rotate_weekdays: bool,
+    ///Duties each person did on every weekday in earlier tables
+    pub weekday_history: HashMap<String, HashMap<Weekday, usize>>,
+    pub pairing: Pairing,
+    ///Among the people whose turn it is, prefer whoever worked the fewest times with the people
+    ///already on the date
+    pub rotate_pairs: bool,
+    ///Earlier dates only set the pace of the task's frequency and nobody is assigned to them
+    pub first_date: Option<NaiveDate>,
+}
+
+///Rows of a task assigned by `scheduler` and the soft constraints it couldn't meet.
+pub struct Assignment {
+    pub rows: Vec<Row>,
+    pub unsatisfied: Vec<String>,
+}
+
+pub fn schedule_dates(
+    scheduler: &dyn Scheduler,
+    people: &[Person],
+    holidays: &[HebDate],
     start_date: &NaiveDate,
     time_period: usize,
-) -> Vec<Row> {
+    calendar: &WorkCalendar,
+    constraints: &Constraints,
+) -> Result<Assignment, Box<dyn std::error::Error>> {
     //remove holidays from the dates list
-    let mut dates = get_dates_list(start_date, time_period);
+    let mut dates = get_dates_list(start_date, time_period, calendar);
     let holidays: Vec<NaiveDate> = holidays.iter().map(|f| f.date).collect();
     dates.retain(|d| !holidays.contains(d));
+    let mut dates = filter_frequency(dates, &constraints.frequency);
+    if let Some(first_date) = constraints.first_date {
+        dates.retain(|d| *d >= first_date);
+    }
+    if people.is_empty() && !dates.is_empty() {
+        return Err("Nobody is on the roster.".into());
+    }
+    let rows = scheduler.assign(people, &dates, constraints)?;
+    let unsatisfied = unsatisfied(people, &dates, &rows, constraints);
+    Ok(Assignment { rows, unsatisfied })
+}
+
+//Duties everyone starts with. Newcomers start level with whoever did the fewest duties, relative
+//to their weight, instead of catching up on all of them.
+fn starting_duties(people: &[Person], constraints: &Constraints) -> Vec<f64> {
+    let least = people
+        .iter()
+        .filter(|p| p.weight > 0.0)
+        .filter_map(|p| Some(*constraints.history.get(&p.name)? as f64 / p.weight))
+        .min_by(|a, b| a.partial_cmp(b).unwrap())
+        .unwrap_or(0.0);
+    people
+        .iter()
+        .map(|p| match constraints.history.get(&p.name) {
+            Some(done) => *done as f64,
+            None => least * p.weight,
+        })
+        .collect()
+}
 
-    let mut rows = vec![];
-    let mut iter = people.iter();
-    for date in dates {
-        if let Some(person) = iter.next() {
-            rows.push(Row {
-                person: person.clone(),
-                date,
+//Dates without enough people, unless the roster itself is smaller than the staffing
+fn check_staffing(
+    people: &[Person],
+    rows: &[Row],
+    dates: &[NaiveDate],
+    constraints: &Constraints,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let roster = people.iter().filter(|p| p.weight > 0.0).count();
+    let unfilled: Vec<String> = dates
+        .iter()
+        .filter_map(|date| {
+            let needed = constraints.staffing.people_on(date);
+            let assigned: Vec<usize> = rows
+                .iter()
+                .filter(|r| r.date == *date)
+                .filter_map(|r| people.iter().position(|p| p.name == r.person.name))
+                .collect();
+            (assigned.len() < needed.min(roster))
+                .then(|| unfilled_date(people, date, needed, &assigned, constraints))
+        })
+        .collect();
+    if !unfilled.is_empty() {
+        return Err(format!(
+            "Not enough people can be scheduled on some dates:\n{}",
+            unfilled.join("\n")
+        )
+        .into());
+    }
+    Ok(())
+}
+
+//Why a person can't be scheduled on a date, if they can't
+fn blocked(person: &Person, date: &NaiveDate, constraints: &Constraints) -> Option<String> {
+    if person.forbidden.contains(&date.weekday()) {
+        Some(format!("{} never works on {}", person.name, date.weekday()))
+    } else if !constraints.availability.is_available(&person.name, date) {
+        Some(format!("{} is unavailable", person.name))
+    } else {
+        None
+    }
+}
+
+//Indices of `p` and everyone on the roster who has to be on the same dates as them
+fn group(people: &[Person], p: usize, constraints: &Constraints) -> Vec<usize> {
+    let mut group = vec![p];
+    for partner in constraints.pairing.partners(&people[p].name) {
+        if let Some(i) = people
+            .iter()
+            .position(|q| q.name == partner && q.weight > 0.0)
+        {
+            group.push(i);
+        }
+    }
+    group
+}
+
+//`p` and the people who have to come with them to a date, or why they can't be assigned to it
+fn fits(
+    people: &[Person],
+    date: &NaiveDate,
+    assigned: &[usize],
+    p: usize,
+    needed: usize,
+    constraints: &Constraints,
+) -> Result<Vec<usize>, String> {
+    let group = group(people, p, constraints);
+    for q in &group {
+        let reason = match assigned.contains(q) {
+            true => Some(format!("{} is already assigned", people[*q].name)),
+            false => blocked(&people[*q], date, constraints),
+        };
+        if let Some(reason) = reason {
+            return Err(match *q == p {
+                true => reason,
+                false => format!(
+                    "{} works only with {} ({})",
+                    people[p].name, people[*q].name, reason
+                ),
             });
-        } else {
-            iter = people.iter();
-            if let Some(person) = iter.next() {
-                rows.push(Row {
-                    person: person.clone(),
-                    date,
-                });
-            } else {
-                panic!("People' vector is empty");
+        }
+    }
+    if assigned.len() + group.len() > needed {
+        let partners: Vec<&str> = group[1..]
+            .iter()
+            .map(|q| people[*q].name.as_str())
+            .collect();
+        return Err(format!(
+            "{} works only with {} and there's no room for them",
+            people[p].name,
+            partners.join(" and ")
+        ));
+    }
+    let mut on_date = assigned.to_vec();
+    on_date.extend(&group);
+    match pairing_conflict(people, &on_date, needed, constraints) {
+        Some(reason) => Err(reason),
+        None => Ok(group),
+    }
+}
+
+///Why `name` can't be assigned to `date` with `others` already on it, if they can't.
+pub fn conflict(
+    people: &[Person],
+    name: &str,
+    date: &NaiveDate,
+    others: &[&str],
+    constraints: &Constraints,
+) -> Option<String> {
+    let p = match people.iter().position(|p| p.name == name) {
+        Some(p) => p,
+        None => return Some(format!("{} isn't on the roster", name)),
+    };
+    let assigned: Vec<usize> = others
+        .iter()
+        .filter_map(|other| people.iter().position(|q| q.name == *other))
+        .collect();
+    let needed = constraints.staffing.people_on(date);
+    fits(people, date, &assigned, p, needed, constraints).err()
+}
+
+//Why the people on a date break the pairing rules, if they do
+fn pairing_conflict(
+    people: &[Person],
+    on_date: &[usize],
+    needed: usize,
+    constraints: &Constraints,
+) -> Option<String> {
+    for (i, p) in on_date.iter().enumerate() {
+        for other in &on_date[i + 1..] {
+            if constraints
+                .pairing
+                .apart(&people[*p].name, &people[*other].name)
+            {
+                return Some(format!(
+                    "{} and {} are kept apart",
+                    people[*p].name, people[*other].name
+                ));
             }
         }
+        if let Some(partner) = group(people, *p, constraints)
+            .iter()
+            .find(|partner| !on_date.contains(partner))
+        {
+            return Some(format!(
+                "{} works only with {}",
+                people[*p].name, people[*partner].name
+            ));
+        }
+    }
+    if needed > 1 && on_date.len() == needed && on_date.iter().all(|p| people[*p].newcomer) {
+        return Some("newcomers need someone who isn't one".to_string());
     }
-    rows
+    None
 }
 
-fn get_dates_list(start_date: &NaiveDate, time_period: usize) -> Vec<NaiveDate> {
-    start_date
-        .iter_days()
-        .filter(|p| {
-            p.weekday() != Weekday::Thu
-                && p.weekday() != Weekday::Fri
-                && p.weekday() != Weekday::Sat
+//Describe a date that didn't get enough people and who couldn't take it
+fn unfilled_date(
+    people: &[Person],
+    date: &NaiveDate,
+    needed: usize,
+    assigned: &[usize],
+    constraints: &Constraints,
+) -> String {
+    let mut reasons: Vec<String> = vec![];
+    for (p, person) in people.iter().enumerate() {
+        if person.weight <= 0.0 || assigned.contains(&p) {
+            continue;
+        }
+        if let Err(reason) = fits(people, date, assigned, p, needed, constraints) {
+            if !reasons.contains(&reason) {
+                reasons.push(reason);
+            }
+        }
+    }
+    format!(
+        "  {} ({}): {} of {} people assigned. {}",
+        date,
+        date.weekday(),
+        assigned.len(),
+        needed,
+        reasons.join(", ")
+    )
+}
+
+//Weekday numbers separated by spaces or ';', 1 is Sunday. A wrong number is returned as the error.
+fn parse_weekdays(days: &str) -> Result<Vec<Weekday>, String> {
+    days.split([' ', ';'])
+        .filter(|d| !d.is_empty())
+        .map(|day| match day.parse::<usize>() {
+            Ok(number) if (1..=7).contains(&number) => Ok(int_to_weekday(number)),
+            _ => Err(day.to_string()),
         })
+        .collect()
+}
+
+//Keep only the workdays a task is scheduled on
+fn filter_frequency(dates: Vec<NaiveDate>, frequency: &Frequency) -> Vec<NaiveDate> {
+    match frequency {
+        Frequency::Daily => dates,
+        Frequency::Weekly => {
+            let mut last_week = None;
+            dates
+                .into_iter()
+                .filter(|d| {
+                    let week = Some(d.week(Weekday::Sun).first_day());
+                    let is_first = week != last_week;
+                    last_week = week;
+                    is_first
+                })
+                .collect()
+        }
+        Frequency::Every(n) => dates.into_iter().step_by((*n).max(1)).collect(),
+    }
+}
+
+fn get_dates_list(
+    start_date: &NaiveDate,
+    time_period: usize,
+    calendar: &WorkCalendar,
+) -> Vec<NaiveDate> {
+    calendar
+        .workdays_from(start_date)
         .take(time_period)
         .collect()
 }
@@ -71,38 +337,390 @@ fn get_dates_list(start_date: &NaiveDate, time_period: usize) -> Vec<NaiveDate>
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::Datelike;
+
+    //Rows of the default scheduler
+    fn get_dates(
+        people: &[Person],
+        holidays: &[HebDate],
+        start_date: &NaiveDate,
+        time_period: usize,
+        calendar: &WorkCalendar,
+        constraints: &Constraints,
+    ) -> Result<Vec<Row>, Box<dyn std::error::Error>> {
+        let assignment = schedule_dates(
+            &RoundRobin,
+            people,
+            holidays,
+            start_date,
+            time_period,
+            calendar,
+            constraints,
+        )?;
+        Ok(assignment.rows)
+    }
+
+    fn calendar() -> WorkCalendar {
+        WorkCalendar::new(vec![Weekday::Thu, Weekday::Fri, Weekday::Sat], vec![])
+    }
 
     #[test]
     fn dates_list() {
-        let start_date = NaiveDate::from_ymd(2000, 1, 1);
+        let start_date = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
         let time_period = 7;
         let res: Vec<NaiveDate> = start_date.iter_days().take(time_period).collect();
         assert!(res.iter().any(|p| p.weekday() == Weekday::Sat));
-        let res = get_dates_list(&start_date, time_period);
+        let res = get_dates_list(&start_date, time_period, &calendar());
         assert!(!res.iter().any(|p| p.weekday() == Weekday::Sat))
     }
 
+    #[test]
+    fn dates_list_follows_weekend() {
+        let start_date = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+        let calendar = WorkCalendar::new(vec![Weekday::Fri, Weekday::Sat], vec![]);
+        let res = get_dates_list(&start_date, 10, &calendar);
+        assert!(res.iter().any(|p| p.weekday() == Weekday::Thu));
+        assert!(!res.iter().any(|p| p.weekday() == Weekday::Fri));
+    }
+
     #[test]
     fn dates_filtering() {
         let people = vec![
             Person {
                 name: "amichai".to_string(),
                 phone: "00000".to_string(),
+                ..Default::default()
             },
             Person {
                 name: "Joe".to_string(),
                 phone: "333".to_string(),
+                ..Default::default()
             },
         ];
-        let test_date = NaiveDate::from_ymd(2000, 3, 1);
+        let test_date = NaiveDate::from_ymd_opt(2000, 3, 1).unwrap();
         let holidays = vec![HebDate {
             date: test_date,
             title: "purim".to_string(),
+            category: None,
         }];
-        let start_date = NaiveDate::from_ymd(2000, 1, 1);
+        let start_date = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
         let time_period = 180;
-        let res = get_dates(&people, &holidays, &start_date, time_period);
+        let res = get_dates(
+            &people,
+            &holidays,
+            &start_date,
+            time_period,
+            &calendar(),
+            &Constraints::default(),
+        )
+        .unwrap();
         assert!(!res.iter().any(|p| p.date == test_date));
-        assert!(res.iter().next().unwrap().person.name == "amichai".to_string());
+        assert!(res.first().unwrap().person.name == "amichai");
+    }
+
+    #[test]
+    fn several_people_per_day() {
+        let people: Vec<Person> = ["a", "b", "c"]
+            .iter()
+            .map(|name| Person {
+                name: name.to_string(),
+                phone: "0".to_string(),
+                ..Default::default()
+            })
+            .collect();
+        //2000-01-02 is a Sunday
+        let staffing = Staffing {
+            per_day: 2,
+            per_weekday: [(Weekday::Mon, 1), (Weekday::Tue, 5)].into_iter().collect(),
+        };
+        let start_date = NaiveDate::from_ymd_opt(2000, 1, 2).unwrap();
+        let res = get_dates(
+            &people,
+            &[],
+            &start_date,
+            3,
+            &calendar(),
+            &Constraints {
+                staffing,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let names: Vec<(u32, &str)> = res
+            .iter()
+            .map(|r| (r.date.day(), r.person.name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![(2, "a"), (2, "b"), (3, "c"), (4, "a"), (4, "b"), (4, "c")]
+        );
+    }
+
+    #[test]
+    fn task_frequency() {
+        //Sun-Wed workdays, 2000-01-02 is a Sunday
+        let dates = get_dates_list(
+            &NaiveDate::from_ymd_opt(2000, 1, 2).unwrap(),
+            12,
+            &calendar(),
+        );
+        let days = |dates: Vec<NaiveDate>| dates.iter().map(|d| d.day()).collect::<Vec<u32>>();
+        assert_eq!(
+            days(filter_frequency(dates.clone(), &Frequency::Weekly)),
+            vec![2, 9, 16]
+        );
+        assert_eq!(
+            days(filter_frequency(dates.clone(), &Frequency::Every(5))),
+            vec![2, 10, 18]
+        );
+        assert_eq!(filter_frequency(dates.clone(), &Frequency::Daily), dates);
+    }
+
+    #[test]
+    fn unavailable_people_keep_their_turn() {
+        let people: Vec<Person> = ["a", "b", "c"]
+            .iter()
+            .map(|name| Person {
+                name: name.to_string(),
+                phone: "0".to_string(),
+                ..Default::default()
+            })
+            .collect();
+        let constraints = Constraints {
+            availability: Availability::parse("name,from,to,weekdays\na,2000-01-02,2000-01-03,\n")
+                .unwrap(),
+            ..Default::default()
+        };
+        //Sun-Wed workdays, 2000-01-02 is a Sunday
+        let start_date = NaiveDate::from_ymd_opt(2000, 1, 2).unwrap();
+        let res = get_dates(&people, &[], &start_date, 6, &calendar(), &constraints).unwrap();
+        let names: Vec<&str> = res.iter().map(|r| r.person.name.as_str()).collect();
+        assert_eq!(names, vec!["b", "c", "a", "b", "c", "a"]);
+    }
+
+    #[test]
+    fn history_evens_out() {
+        let people: Vec<Person> = ["a", "b", "c"]
+            .iter()
+            .map(|name| Person {
+                name: name.to_string(),
+                phone: "0".to_string(),
+                ..Default::default()
+            })
+            .collect();
+        let constraints = Constraints {
+            history: [
+                ("a".to_string(), 2),
+                ("b".to_string(), 0),
+                ("c".to_string(), 1),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let start_date = NaiveDate::from_ymd_opt(2000, 1, 2).unwrap();
+        let res = get_dates(&people, &[], &start_date, 4, &calendar(), &constraints).unwrap();
+        let names: Vec<&str> = res.iter().map(|r| r.person.name.as_str()).collect();
+        assert_eq!(names, vec!["b", "c", "b", "a"]);
+    }
+
+    #[test]
+    fn newcomer_starts_level() {
+        let people: Vec<Person> = ["a", "b", "new"]
+            .iter()
+            .map(|name| Person {
+                name: name.to_string(),
+                phone: "0".to_string(),
+                ..Default::default()
+            })
+            .collect();
+        let constraints = Constraints {
+            history: [("a".to_string(), 10), ("b".to_string(), 10)]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        let start_date = NaiveDate::from_ymd_opt(2000, 1, 2).unwrap();
+        let res = get_dates(&people, &[], &start_date, 6, &calendar(), &constraints).unwrap();
+        let names: Vec<&str> = res.iter().map(|r| r.person.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b", "new", "a", "b", "new"]);
+    }
+
+    #[test]
+    fn weighted_rotation() {
+        let people: Vec<Person> = [("a", 1.0), ("b", 0.5), ("lead", 0.0)]
+            .iter()
+            .map(|(name, weight)| Person {
+                name: name.to_string(),
+                phone: "0".to_string(),
+                weight: *weight,
+                ..Default::default()
+            })
+            .collect();
+        let start_date = NaiveDate::from_ymd_opt(2000, 1, 2).unwrap();
+        let res = get_dates(
+            &people,
+            &[],
+            &start_date,
+            6,
+            &calendar(),
+            &Constraints::default(),
+        )
+        .unwrap();
+        let names: Vec<&str> = res.iter().map(|r| r.person.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b", "a", "a", "b", "a"]);
+    }
+
+    #[test]
+    fn rotate_weekdays() {
+        let people: Vec<Person> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|name| Person {
+                name: name.to_string(),
+                phone: "0".to_string(),
+                ..Default::default()
+            })
+            .collect();
+        //Four people and four workdays pin everyone to a weekday unless they are rotated
+        let start_date = NaiveDate::from_ymd_opt(2000, 1, 2).unwrap();
+        let names = |constraints: &Constraints| {
+            get_dates(&people, &[], &start_date, 8, &calendar(), constraints)
+                .unwrap()
+                .iter()
+                .map(|r| r.person.name.clone())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(
+            names(&Constraints::default()),
+            vec!["a", "b", "c", "d", "a", "b", "c", "d"]
+        );
+        let constraints = Constraints {
+            rotate_weekdays: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            names(&constraints),
+            vec!["a", "b", "c", "d", "b", "a", "d", "c"]
+        );
+    }
+
+    #[test]
+    fn weekday_preferences() {
+        let person = |name: &str, preferred: Vec<Weekday>, forbidden: Vec<Weekday>| Person {
+            name: name.to_string(),
+            preferred,
+            forbidden,
+            ..Default::default()
+        };
+        let people = vec![
+            person("a", vec![], vec![Weekday::Mon]),
+            person("b", vec![], vec![]),
+            person("c", vec![Weekday::Mon], vec![]),
+        ];
+        //Sun-Wed workdays, 2000-01-02 is a Sunday
+        let start_date = NaiveDate::from_ymd_opt(2000, 1, 2).unwrap();
+        let res = get_dates(
+            &people,
+            &[],
+            &start_date,
+            6,
+            &calendar(),
+            &Constraints::default(),
+        )
+        .unwrap();
+        let names: Vec<(Weekday, &str)> = res
+            .iter()
+            .map(|r| (r.date.weekday(), r.person.name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                (Weekday::Sun, "a"),
+                (Weekday::Mon, "c"),
+                (Weekday::Tue, "b"),
+                (Weekday::Wed, "a"),
+                (Weekday::Sun, "c"),
+                (Weekday::Mon, "b"),
+            ]
+        );
+
+        //Nobody left for Mondays
+        let people = vec![
+            person("a", vec![], vec![Weekday::Mon]),
+            person("b", vec![], vec![Weekday::Mon, Weekday::Tue]),
+        ];
+        let err = get_dates(
+            &people,
+            &[],
+            &start_date,
+            4,
+            &calendar(),
+            &Constraints::default(),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("2000-01-03 (Mon): 0 of 1 people assigned"));
+        assert!(err.contains("a never works on Mon, b never works on Mon"));
+        assert!(!err.contains("2000-01-04"));
+    }
+
+    #[test]
+    fn pairing_rules() {
+        let people: Vec<Person> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|name| Person {
+                name: name.to_string(),
+                newcomer: *name == "c" || *name == "d",
+                ..Default::default()
+            })
+            .collect();
+        let staffing = Staffing {
+            per_day: 2,
+            ..Default::default()
+        };
+        let start_date = NaiveDate::from_ymd_opt(2000, 1, 2).unwrap();
+        let days = |constraints: &Constraints| {
+            let rows = get_dates(&people, &[], &start_date, 6, &calendar(), constraints).unwrap();
+            rows.chunks(2)
+                .map(|day| format!("{}{}", day[0].person.name, day[1].person.name))
+                .collect::<Vec<String>>()
+        };
+        //The newcomers c and d never work alone together
+        let constraints = Constraints {
+            staffing: staffing.clone(),
+            ..Default::default()
+        };
+        assert_eq!(days(&constraints), vec!["ab", "ca", "db", "ca", "db", "ca"]);
+
+        let constraints = Constraints {
+            staffing: staffing.clone(),
+            pairing: Pairing::parse("rule,first,second\ntogether,a,d\napart,a,b\n").unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(days(&constraints), vec!["ad", "bc", "ad", "bc", "ad", "bc"]);
+
+        let constraints = Constraints {
+            staffing,
+            rotate_pairs: true,
+            ..Default::default()
+        };
+        assert_eq!(days(&constraints), vec!["ab", "ca", "db", "cb", "da", "cb"]);
+
+        //d can't come without a, who is away on the first Sunday
+        let constraints = Constraints {
+            staffing: Staffing {
+                per_day: 3,
+                ..Default::default()
+            },
+            pairing: Pairing::parse("rule,first,second\ntogether,a,d\n").unwrap(),
+            availability: Availability::parse("name,from,to,weekdays\na,2000-01-02,2000-01-02,\n")
+                .unwrap(),
+            ..Default::default()
+        };
+        let err = get_dates(&people, &[], &start_date, 1, &calendar(), &constraints)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("2 of 3 people assigned"));
+        assert!(err.contains("d works only with a (a is unavailable)"));
     }
 }
@@ -0,0 +1,96 @@
+use csv::Reader;
+use serde::Deserialize;
+
+//Row of pairing.csv
+#[derive(Deserialize)]
+struct PairingRaw {
+    rule: String,
+    first: String,
+    second: String,
+}
+
+///Who must or must not share a date.
+#[derive(Debug, Clone, Default)]
+pub struct Pairing {
+    together: Vec<(String, String)>,
+    apart: Vec<(String, String)>,
+}
+
+impl Pairing {
+    pub fn parse(csv: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut rdr = Reader::from_reader(csv.as_bytes());
+        let mut pairing = Self::default();
+        for row in rdr.deserialize() {
+            let row: PairingRaw = row?;
+            let pair = (row.first.trim().to_string(), row.second.trim().to_string());
+            match row.rule.trim() {
+                "together" => pairing.together.push(pair),
+                "apart" => pairing.apart.push(pair),
+                rule => {
+                    return Err(format!(
+                        "Wrong rule \"{}\" for {} and {} in pairing file. Expecting \"together\" or \"apart\".",
+                        rule, pair.0, pair.1
+                    )
+                    .into())
+                }
+            }
+        }
+        Ok(pairing)
+    }
+
+    ///A missing file means there are no rules.
+    pub fn from_file(file: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if !std::path::Path::new(file).exists() {
+            return Ok(Self::default());
+        }
+        Self::parse(&std::fs::read_to_string(file)?)
+    }
+
+    pub fn apart(&self, first: &str, second: &str) -> bool {
+        self.apart
+            .iter()
+            .any(|(a, b)| (a == first && b == second) || (a == second && b == first))
+    }
+
+    ///Everyone who has to be on the same dates as `name`, directly or through someone else.
+    pub fn partners(&self, name: &str) -> Vec<String> {
+        let mut group = vec![name.to_string()];
+        let mut i = 0;
+        while i < group.len() {
+            for (a, b) in &self.together {
+                for (one, other) in [(a, b), (b, a)] {
+                    if *one == group[i] && !group.contains(other) {
+                        group.push(other.clone());
+                    }
+                }
+            }
+            i += 1;
+        }
+        group.remove(0);
+        group
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules() {
+        let pairing = Pairing::parse(
+            "rule,first,second
+together,Joe,Ann
+together,Ann,Kaladin
+apart,Joe,Maddy
+",
+        )
+        .unwrap();
+        assert_eq!(pairing.partners("Joe"), vec!["Ann", "Kaladin"]);
+        assert_eq!(pairing.partners("Kaladin"), vec!["Ann", "Joe"]);
+        assert!(pairing.partners("Maddy").is_empty());
+        assert!(pairing.apart("Maddy", "Joe"));
+        assert!(!pairing.apart("Maddy", "Ann"));
+
+        assert!(Pairing::parse("rule,first,second\nnear,Joe,Ann\n").is_err());
+    }
+}
@@ -1,23 +1,166 @@
+use super::parse_weekdays;
+use chrono::Weekday;
 use csv::Reader;
-use serde::{Deserialize, Serialize};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Person {
     pub name: String,
     pub phone: String,
+    ///Share of the duties compared to everyone else. 0.5 is half as many, 0 is none at all.
+    #[serde(default = "default_weight", deserialize_with = "weight_or_default")]
+    pub weight: f64,
+    ///Weekdays the person is scheduled on when it's their turn anyway
+    #[serde(
+        default,
+        rename = "preferred_days",
+        deserialize_with = "weekdays",
+        serialize_with = "weekday_numbers"
+    )]
+    pub preferred: Vec<Weekday>,
+    ///Weekdays the person is never scheduled on
+    #[serde(
+        default,
+        rename = "forbidden_days",
+        deserialize_with = "weekdays",
+        serialize_with = "weekday_numbers"
+    )]
+    pub forbidden: Vec<Weekday>,
+    ///Newcomers are always paired with someone who isn't one
+    #[serde(default, deserialize_with = "flag")]
+    pub newcomer: bool,
+    ///Address of email reminders
+    #[serde(default)]
+    pub email: String,
+    ///How the person gets reminders
+    #[serde(default, deserialize_with = "channel_or_default")]
+    pub channel: Channel,
+    ///Telegram chat or Matrix room of chat bot reminders
+    #[serde(default)]
+    pub chat_id: String,
 }
 
-pub fn parse_candidates_from_file(file: &str) -> Vec<Person> {
-    let data = std::fs::read_to_string(file).expect("Could not read candidates file.");
+///Where reminders are sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    ///The configured transport, SMS by default
+    #[default]
+    Sms,
+    Email,
+    Both,
+}
+
+impl Default for Person {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            phone: String::new(),
+            weight: default_weight(),
+            preferred: vec![],
+            forbidden: vec![],
+            newcomer: false,
+            email: String::new(),
+            channel: Channel::default(),
+            chat_id: String::new(),
+        }
+    }
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+//An empty weight cell is a regular weight
+fn weight_or_default<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<f64>::deserialize(deserializer)?.unwrap_or_else(default_weight))
+}
+
+//An empty channel cell is SMS
+fn channel_or_default<'de, D>(deserializer: D) -> Result<Channel, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let channel = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
+    match channel.trim().to_lowercase().as_str() {
+        "" | "sms" => Ok(Channel::Sms),
+        "email" => Ok(Channel::Email),
+        "both" => Ok(Channel::Both),
+        channel => Err(D::Error::custom(format!(
+            "Wrong channel \"{}\". Expecting sms, email or both.",
+            channel
+        ))),
+    }
+}
+
+//"yes", "true" or "1", an empty cell is no
+fn flag<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let flag = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
+    match flag.trim().to_lowercase().as_str() {
+        "yes" | "true" | "1" => Ok(true),
+        "" | "no" | "false" | "0" => Ok(false),
+        flag => Err(D::Error::custom(format!(
+            "Wrong value \"{}\". Expecting yes or no.",
+            flag
+        ))),
+    }
+}
+
+//Weekday numbers separated by spaces or ';', 1 is Sunday
+fn weekdays<'de, D>(deserializer: D) -> Result<Vec<Weekday>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let days = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
+    parse_weekdays(&days).map_err(|day| {
+        D::Error::custom(format!(
+            "Wrong weekday \"{}\". Expecting 1 (Sunday) to 7 (Saturday).",
+            day
+        ))
+    })
+}
+
+fn weekday_numbers<S>(days: &[Weekday], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let days: Vec<String> = days
+        .iter()
+        .map(|d| d.number_from_sunday().to_string())
+        .collect();
+    serializer.serialize_str(&days.join(" "))
+}
+
+pub fn parse_candidates_from_file(file: &str) -> Result<Vec<Person>, Box<dyn std::error::Error>> {
+    let data = std::fs::read_to_string(file)
+        .map_err(|e| format!("Could not read candidates file {}: {}", file, e))?;
     let mut people = Vec::new();
     let mut rdr = Reader::from_reader(data.as_bytes());
-    let iter = rdr
-        .deserialize()
-        .map(|x: Result<Person, csv::Error>| x.unwrap());
-    for row in iter {
-        people.push(row);
+    for row in rdr.deserialize() {
+        let person: Person = row?;
+        if !person.weight.is_finite() || person.weight < 0.0 {
+            return Err(format!(
+                "{} has a wrong weight in {}. Expecting a number of 0 or more.",
+                person.name, file
+            )
+            .into());
+        }
+        //Tables, switches and the solver tell people apart by name
+        if people.iter().any(|p: &Person| p.name == person.name) {
+            return Err(format!("{} is in {} twice.", person.name, file).into());
+        }
+        people.push(person);
+    }
+    if people.is_empty() {
+        return Err(format!("There is nobody in {}.", file).into());
     }
-    people
+    Ok(people)
 }
 
 #[cfg(test)]
@@ -28,14 +171,96 @@ mod tests {
         let s = r#"name,phone
 Joe,058"#;
         std::fs::write("./test.csv", s).expect("Failed to write to test file");
-        let vec = vec![Person {
+        let vec = [Person {
             name: "Joe".to_string(),
             phone: "058".to_string(),
+            ..Default::default()
         }];
         let parsed = parse_candidates_from_file("./test.csv").expect("failed test");
         assert_eq!(vec.len(), parsed.len());
         assert_eq!(vec[0].name, parsed[0].name);
         assert_eq!(vec[0].phone, parsed[0].phone);
-        std::fs::remove_file("./test").expect("Could nout remove file");
+        std::fs::remove_file("./test.csv").expect("Could nout remove file");
+    }
+
+    #[test]
+    fn parse_weights() {
+        let s = "name,phone,weight
+Joe,058,0.5
+Ann,059,
+Lead,060,0";
+        std::fs::write("./test_weights.csv", s).unwrap();
+        let parsed = parse_candidates_from_file("./test_weights.csv");
+        std::fs::remove_file("./test_weights.csv").unwrap();
+        let weights: Vec<f64> = parsed.unwrap().iter().map(|p| p.weight).collect();
+        assert_eq!(weights, vec![0.5, 1.0, 0.0]);
+
+        for weight in ["-1", "NaN", "inf"] {
+            std::fs::write(
+                "./test_bad_weight.csv",
+                format!("name,phone,weight\nJoe,058,{}\n", weight),
+            )
+            .unwrap();
+            let parsed = parse_candidates_from_file("./test_bad_weight.csv");
+            std::fs::remove_file("./test_bad_weight.csv").unwrap();
+            assert!(parsed.is_err());
+        }
+    }
+
+    #[test]
+    fn duplicate_names() {
+        std::fs::write("./test_duplicates.csv", "name,phone\nJoe,058\nJoe,059\n").unwrap();
+        let parsed = parse_candidates_from_file("./test_duplicates.csv");
+        std::fs::remove_file("./test_duplicates.csv").unwrap();
+        assert_eq!(
+            parsed.unwrap_err().to_string(),
+            "Joe is in ./test_duplicates.csv twice."
+        );
+    }
+
+    #[test]
+    fn empty_roster() {
+        std::fs::write("./test_empty_roster.csv", "name,phone\n").unwrap();
+        let parsed = parse_candidates_from_file("./test_empty_roster.csv");
+        std::fs::remove_file("./test_empty_roster.csv").unwrap();
+        assert_eq!(
+            parsed.unwrap_err().to_string(),
+            "There is nobody in ./test_empty_roster.csv."
+        );
+    }
+
+    #[test]
+    fn parse_weekday_preferences() {
+        let s = "name,phone,preferred_days,forbidden_days,newcomer,email,channel,chat_id
+Joe,058,4,2,,,,
+Ann,059,,1;7,yes,ann@example.com,Email,
+Kaladin,060,,,no,k@example.com,both,-100123";
+        std::fs::write("./test_weekdays.csv", s).unwrap();
+        let parsed = parse_candidates_from_file("./test_weekdays.csv");
+        std::fs::remove_file("./test_weekdays.csv").unwrap();
+        let parsed = parsed.unwrap();
+        assert_eq!(parsed[0].preferred, vec![Weekday::Wed]);
+        assert_eq!(parsed[0].forbidden, vec![Weekday::Mon]);
+        assert_eq!(parsed[1].forbidden, vec![Weekday::Sun, Weekday::Sat]);
+        assert!(parsed[2].preferred.is_empty() && parsed[2].forbidden.is_empty());
+        assert_eq!(
+            parsed.iter().map(|p| p.newcomer).collect::<Vec<bool>>(),
+            vec![false, true, false]
+        );
+        assert_eq!(parsed[1].email, "ann@example.com");
+        assert_eq!(parsed[2].chat_id, "-100123");
+        assert_eq!(
+            parsed.iter().map(|p| p.channel).collect::<Vec<Channel>>(),
+            vec![Channel::Sms, Channel::Email, Channel::Both]
+        );
+
+        std::fs::write(
+            "./test_bad_weekdays.csv",
+            "name,phone,forbidden_days\nJoe,058,8\n",
+        )
+        .unwrap();
+        let parsed = parse_candidates_from_file("./test_bad_weekdays.csv");
+        std::fs::remove_file("./test_bad_weekdays.csv").unwrap();
+        assert!(parsed.is_err());
     }
 }
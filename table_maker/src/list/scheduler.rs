@@ -0,0 +1,249 @@
+use super::{check_staffing, fits, starting_duties, Constraints, Person, Row, Solver};
+use chrono::{Datelike, NaiveDate, Weekday};
+use std::collections::{HashMap, VecDeque};
+use table_configs::config::SchedulerKind;
+
+///Assigns the people of a task's roster to its dates.
+pub trait Scheduler {
+    ///Rows for `dates`, in date order. Fails when the hard constraints (staffing, forbidden
+    ///weekdays, unavailability and pairing rules) can't all be met.
+    fn assign(
+        &self,
+        people: &[Person],
+        dates: &[NaiveDate],
+        constraints: &Constraints,
+    ) -> Result<Vec<Row>, Box<dyn std::error::Error>>;
+}
+
+pub fn scheduler_for(kind: &SchedulerKind) -> Box<dyn Scheduler> {
+    match kind {
+        SchedulerKind::RoundRobin => Box::new(RoundRobin),
+        SchedulerKind::Solver { seed, iterations } => Box::new(Solver {
+            seed: *seed,
+            iterations: *iterations,
+        }),
+    }
+}
+
+///Rotation in roster order, the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RoundRobin;
+
+impl Scheduler for RoundRobin {
+    fn assign(
+        &self,
+        people: &[Person],
+        dates: &[NaiveDate],
+        constraints: &Constraints,
+    ) -> Result<Vec<Row>, Box<dyn std::error::Error>> {
+        let mut rows = vec![];
+        let mut duties = starting_duties(people, constraints);
+        //Whoever's next duty is due first relative to their weight goes next, so duties are proportional
+        //to the weights and evenly spaced. Ties are broken by the rotation's order.
+        //Whoever is unavailable stays at the front of the rotation and catches up afterwards.
+        //Forbidden weekdays are never assigned, preferred ones go first among the people whose turn it is,
+        //then whoever worked the fewest times with the people already on the date.
+        //People who have to be on the same dates are assigned together, and nobody is put on a
+        //date with someone they're kept apart from.
+        let mut weekdays = constraints.weekday_history.clone();
+        let mut pairs: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut rotation: VecDeque<usize> = (0..people.len()).collect();
+        for date in dates {
+            let mut assigned: Vec<usize> = vec![];
+            let needed = constraints.staffing.people_on(date);
+            while assigned.len() < needed {
+                //Nobody is assigned twice to the same date
+                let next = rotation
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| people[**p].weight > 0.0 && !assigned.contains(p))
+                    .filter_map(|(i, p)| {
+                        let group = fits(people, date, &assigned, *p, needed, constraints).ok()?;
+                        Some((i, *p, group))
+                    })
+                    .map(|(i, p, group)| {
+                        let person = &people[p];
+                        let on_weekday = match constraints.rotate_weekdays {
+                            true => weekday_count(&weekdays, &person.name, date.weekday()),
+                            false => 0,
+                        };
+                        let together = match constraints.rotate_pairs {
+                            true => assigned.iter().map(|a| pair_count(&pairs, p, *a)).sum(),
+                            false => 0,
+                        };
+                        let other_day = !person.preferred.contains(&date.weekday());
+                        let due = (duties[p] + 1.0) / person.weight;
+                        (i, group, due, (other_day, together, on_weekday))
+                    })
+                    .min_by(|(_, _, a, a_day), (_, _, b, b_day)| {
+                        a.partial_cmp(b).unwrap().then(a_day.cmp(b_day))
+                    })
+                    .map(|(_, group, _, _)| group);
+                let group = match next {
+                    Some(group) => group,
+                    None => break,
+                };
+                for p in group {
+                    rotation.retain(|q| *q != p);
+                    rotation.push_back(p);
+                    for a in &assigned {
+                        *pairs.entry((p.min(*a), p.max(*a))).or_insert(0) += 1;
+                    }
+                    assigned.push(p);
+                    duties[p] += 1.0;
+                    *weekdays
+                        .entry(people[p].name.clone())
+                        .or_default()
+                        .entry(date.weekday())
+                        .or_insert(0) += 1;
+                    rows.push(Row {
+                        person: people[p].clone(),
+                        date: *date,
+                        short_day: false,
+                        task: String::new(),
+                    });
+                }
+            }
+        }
+        check_staffing(people, &rows, dates, constraints)?;
+        Ok(rows)
+    }
+}
+
+fn pair_count(pairs: &HashMap<(usize, usize), usize>, a: usize, b: usize) -> usize {
+    pairs.get(&(a.min(b), a.max(b))).cloned().unwrap_or(0)
+}
+
+fn weekday_count(
+    weekdays: &HashMap<String, HashMap<Weekday, usize>>,
+    name: &str,
+    weekday: Weekday,
+) -> usize {
+    weekdays
+        .get(name)
+        .and_then(|days| days.get(&weekday))
+        .cloned()
+        .unwrap_or(0)
+}
+
+//Soft constraints the rows don't meet: fair shares, preferred days and the weekday and pair
+//rotations
+pub(super) fn unsatisfied(
+    people: &[Person],
+    dates: &[NaiveDate],
+    rows: &[Row],
+    constraints: &Constraints,
+) -> Vec<String> {
+    let mut report = vec![];
+    let position = |name: &str| people.iter().position(|p| p.name == name);
+    let mut duties = starting_duties(people, constraints);
+    let mut new = vec![0; people.len()];
+    for row in rows {
+        if let Some(p) = position(&row.person.name) {
+            duties[p] += 1.0;
+            new[p] += 1;
+        }
+    }
+
+    //Nobody should be more than one duty ahead of the least loaded person, relative to weight
+    let least = people
+        .iter()
+        .zip(&duties)
+        .filter(|(p, _)| p.weight > 0.0)
+        .map(|(p, d)| d / p.weight)
+        .min_by(|a, b| a.partial_cmp(b).unwrap())
+        .unwrap_or(0.0);
+    for (p, person) in people.iter().enumerate() {
+        if person.weight > 0.0 && duties[p] / person.weight - least > 1.0 / person.weight + 1e-9 {
+            report.push(format!(
+                "{} has {} duties, more than their share",
+                person.name, new[p]
+            ));
+        }
+    }
+
+    for (p, person) in people.iter().enumerate() {
+        if person.preferred.is_empty() {
+            continue;
+        }
+        let other_days = rows
+            .iter()
+            .filter(|r| r.person.name == person.name)
+            .filter(|r| !person.preferred.contains(&r.date.weekday()))
+            .count();
+        if other_days > 0 {
+            report.push(format!(
+                "{} has {} of {} duties on days they didn't prefer",
+                person.name, other_days, new[p]
+            ));
+        }
+    }
+
+    if constraints.rotate_weekdays {
+        let mut days: Vec<Weekday> = dates.iter().map(|d| d.weekday()).collect();
+        days.sort_by_key(|d| d.num_days_from_sunday());
+        days.dedup();
+        for person in people.iter().filter(|p| p.weight > 0.0) {
+            let counts: Vec<(Weekday, usize)> = days
+                .iter()
+                .filter(|day| !person.forbidden.contains(day))
+                .map(|day| {
+                    let count = rows
+                        .iter()
+                        .filter(|r| r.person.name == person.name && r.date.weekday() == *day)
+                        .count();
+                    (*day, count)
+                })
+                .collect();
+            let most = counts.iter().max_by_key(|(_, count)| *count);
+            let fewest = counts.iter().min_by_key(|(_, count)| *count);
+            if let (Some(most), Some(fewest)) = (most, fewest) {
+                if most.1 > fewest.1 + 1 {
+                    report.push(format!(
+                        "{} has {} duties on {} but {} on {}",
+                        person.name, most.1, most.0, fewest.1, fewest.0
+                    ));
+                }
+            }
+        }
+    }
+    if constraints.rotate_pairs && dates.iter().any(|d| constraints.staffing.people_on(d) > 1) {
+        //Everyone who may work together should do so about as often
+        let on_dates: Vec<Vec<usize>> = dates
+            .iter()
+            .map(|date| {
+                rows.iter()
+                    .filter(|r| r.date == *date)
+                    .filter_map(|r| position(&r.person.name))
+                    .collect()
+            })
+            .collect();
+        let mut pairs = vec![];
+        for (a, first) in people.iter().enumerate() {
+            for (b, second) in people.iter().enumerate().skip(a + 1) {
+                if first.weight <= 0.0
+                    || second.weight <= 0.0
+                    || constraints.pairing.apart(&first.name, &second.name)
+                {
+                    continue;
+                }
+                let count = on_dates
+                    .iter()
+                    .filter(|on_date| on_date.contains(&a) && on_date.contains(&b))
+                    .count();
+                pairs.push((first, second, count));
+            }
+        }
+        let most = pairs.iter().max_by_key(|(_, _, count)| *count);
+        let fewest = pairs.iter().min_by_key(|(_, _, count)| *count);
+        if let (Some(most), Some(fewest)) = (most, fewest) {
+            if most.2 > fewest.2 + 1 {
+                report.push(format!(
+                    "{} and {} work together {} times but {} and {} {} times",
+                    most.0.name, most.1.name, most.2, fewest.0.name, fewest.1.name, fewest.2
+                ));
+            }
+        }
+    }
+    report
+}
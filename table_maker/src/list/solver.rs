@@ -0,0 +1,298 @@
+use super::{
+    blocked, pairing_conflict, starting_duties, Constraints, Person, RoundRobin, Row, Scheduler,
+};
+use chrono::{Datelike, NaiveDate};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+///Local search starting from the round-robin table. Random moves and swaps are kept when they
+///don't make the table worse, so the same seed always gives the same table.
+#[derive(Debug, Clone)]
+pub struct Solver {
+    pub seed: u64,
+    pub iterations: usize,
+}
+
+//Cost of every soft constraint
+const FAIRNESS: f64 = 4.0;
+const SPACING: f64 = 2.0;
+const PREFERENCE: f64 = 1.0;
+const WEEKDAY: f64 = 1.0;
+const PAIRS: f64 = 1.0;
+
+impl Scheduler for Solver {
+    fn assign(
+        &self,
+        people: &[Person],
+        dates: &[NaiveDate],
+        constraints: &Constraints,
+    ) -> Result<Vec<Row>, Box<dyn std::error::Error>> {
+        let rows = RoundRobin.assign(people, dates, constraints)?;
+        if rows.is_empty() {
+            return Ok(rows);
+        }
+        let problem = Problem::new(people, dates, constraints);
+        //Every slot is the index of its date and of the person assigned to it
+        let mut slots: Vec<(usize, usize)> = rows
+            .iter()
+            .map(|r| {
+                let date = dates.iter().position(|d| *d == r.date).unwrap();
+                let person = people.iter().position(|p| p.name == r.person.name).unwrap();
+                (date, person)
+            })
+            .collect();
+        let mut cost = problem.cost(&slots);
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        for _ in 0..self.iterations {
+            let i = rng.gen_range(0..slots.len());
+            let (date, person) = slots[i];
+            let before = slots.clone();
+            if rng.gen_bool(0.5) {
+                //Hand the slot to someone else
+                let other = rng.gen_range(0..people.len());
+                if !problem.can_take(&slots, date, other) {
+                    continue;
+                }
+                slots[i].1 = other;
+            } else {
+                //Swap the people of two dates
+                let j = rng.gen_range(0..slots.len());
+                let (other_date, other) = slots[j];
+                if date == other_date
+                    || !problem.can_take(&slots, date, other)
+                    || !problem.can_take(&slots, other_date, person)
+                {
+                    continue;
+                }
+                slots[i].1 = other;
+                slots[j].1 = person;
+                if !problem.keeps_pairing(&slots, other_date) {
+                    slots = before;
+                    continue;
+                }
+            }
+            if !problem.keeps_pairing(&slots, date) {
+                slots = before;
+                continue;
+            }
+            let new_cost = problem.cost(&slots);
+            if new_cost <= cost {
+                cost = new_cost;
+            } else {
+                slots = before;
+            }
+        }
+        Ok(slots
+            .iter()
+            .map(|(date, person)| Row {
+                person: people[*person].clone(),
+                date: dates[*date],
+                short_day: false,
+                task: String::new(),
+            })
+            .collect())
+    }
+}
+
+//Everything the cost depends on, indexed by person and date so the search is deterministic
+struct Problem<'a> {
+    people: &'a [Person],
+    dates: &'a [NaiveDate],
+    constraints: &'a Constraints,
+    allowed: Vec<Vec<bool>>,
+    duties: Vec<f64>,
+    weekdays: Vec<[usize; 7]>,
+}
+
+impl<'a> Problem<'a> {
+    fn new(people: &'a [Person], dates: &'a [NaiveDate], constraints: &'a Constraints) -> Self {
+        let allowed = dates
+            .iter()
+            .map(|date| {
+                people
+                    .iter()
+                    .map(|p| p.weight > 0.0 && blocked(p, date, constraints).is_none())
+                    .collect()
+            })
+            .collect();
+        let weekdays = people
+            .iter()
+            .map(|p| {
+                let mut counts = [0; 7];
+                if let Some(days) = constraints.weekday_history.get(&p.name) {
+                    for (day, count) in days {
+                        counts[day.num_days_from_sunday() as usize] = *count;
+                    }
+                }
+                counts
+            })
+            .collect();
+        Self {
+            people,
+            dates,
+            constraints,
+            allowed,
+            duties: starting_duties(people, constraints),
+            weekdays,
+        }
+    }
+
+    //The person is allowed on the date and not already assigned to it
+    fn can_take(&self, slots: &[(usize, usize)], date: usize, person: usize) -> bool {
+        self.allowed[date][person] && !slots.contains(&(date, person))
+    }
+
+    //The people on the date still follow the pairing rules
+    fn keeps_pairing(&self, slots: &[(usize, usize)], date: usize) -> bool {
+        let on_date: Vec<usize> = slots
+            .iter()
+            .filter(|(d, _)| *d == date)
+            .map(|(_, p)| *p)
+            .collect();
+        let needed = self.constraints.staffing.people_on(&self.dates[date]);
+        pairing_conflict(self.people, &on_date, needed, self.constraints).is_none()
+    }
+
+    fn cost(&self, slots: &[(usize, usize)]) -> f64 {
+        let mut duties = self.duties.clone();
+        let mut weekdays = self.weekdays.clone();
+        let mut last: Vec<Option<usize>> = vec![None; self.people.len()];
+        let mut pairs = vec![vec![0; self.people.len()]; self.people.len()];
+        let mut cost = 0.0;
+        for (i, (date, p)) in slots.iter().enumerate() {
+            //Slots of a date are next to each other
+            for (other_date, other) in slots[..i].iter().rev() {
+                if other_date != date {
+                    break;
+                }
+                pairs[*p.min(other)][*p.max(other)] += 1;
+            }
+            let person = &self.people[*p];
+            let weekday = self.dates[*date].weekday();
+            duties[*p] += 1.0;
+            weekdays[*p][weekday.num_days_from_sunday() as usize] += 1;
+            //Duties close to each other cost more than the same ones spread out
+            if let Some(last) = last[*p] {
+                cost += SPACING / (date - last) as f64;
+            }
+            last[*p] = Some(*date);
+            if !person.preferred.is_empty() && !person.preferred.contains(&weekday) {
+                cost += PREFERENCE;
+            }
+        }
+        //The sum of squared duties over weights is the lowest when duties follow the weights
+        for (person, duties) in self.people.iter().zip(&duties) {
+            if person.weight > 0.0 {
+                cost += FAIRNESS * duties * duties / person.weight;
+            }
+        }
+        if self.constraints.rotate_weekdays {
+            for counts in &weekdays {
+                cost += WEEKDAY * counts.iter().map(|c| (c * c) as f64).sum::<f64>();
+            }
+        }
+        if self.constraints.rotate_pairs {
+            for counts in &pairs {
+                cost += PAIRS * counts.iter().map(|c| (c * c) as f64).sum::<f64>();
+            }
+        }
+        cost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::list::Availability;
+
+    #[test]
+    fn solver_is_deterministic() {
+        let people: Vec<Person> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|name| Person {
+                name: name.to_string(),
+                preferred: vec![chrono::Weekday::Tue],
+                ..Default::default()
+            })
+            .collect();
+        let dates: Vec<NaiveDate> = NaiveDate::from_ymd_opt(2000, 1, 2)
+            .unwrap()
+            .iter_days()
+            .take(20)
+            .collect();
+        let constraints = Constraints {
+            availability: Availability::parse("name,from,to,weekdays\nb,,,1\n").unwrap(),
+            ..Default::default()
+        };
+        let solve = |seed| {
+            Solver {
+                seed,
+                iterations: 2000,
+            }
+            .assign(&people, &dates, &constraints)
+            .unwrap()
+            .iter()
+            .map(|r| (r.date, r.person.name.clone()))
+            .collect::<Vec<(NaiveDate, String)>>()
+        };
+        let table = solve(7);
+        assert_eq!(table, solve(7));
+        assert_eq!(table.len(), dates.len());
+        //Hard constraints still hold
+        assert!(!table
+            .iter()
+            .any(|(date, name)| name == "b" && date.weekday() == chrono::Weekday::Sun));
+        //Never worse than the rotation it starts from
+        let problem = Problem::new(&people, &dates, &constraints);
+        let slots = |rows: &[(NaiveDate, String)]| -> Vec<(usize, usize)> {
+            rows.iter()
+                .map(|(date, name)| {
+                    (
+                        dates.iter().position(|d| d == date).unwrap(),
+                        people.iter().position(|p| p.name == *name).unwrap(),
+                    )
+                })
+                .collect()
+        };
+        let round_robin: Vec<(NaiveDate, String)> = RoundRobin
+            .assign(&people, &dates, &constraints)
+            .unwrap()
+            .iter()
+            .map(|r| (r.date, r.person.name.clone()))
+            .collect();
+        assert!(problem.cost(&slots(&table)) <= problem.cost(&slots(&round_robin)));
+    }
+
+    //ChaCha8 gives the same numbers on every platform and release, so a seed always makes this table
+    #[test]
+    fn seeded_output_is_pinned() {
+        use chrono::Weekday;
+        let people: Vec<Person> = [
+            ("a", Weekday::Mon),
+            ("b", Weekday::Tue),
+            ("c", Weekday::Tue),
+        ]
+        .iter()
+        .map(|(name, day)| Person {
+            name: name.to_string(),
+            preferred: vec![*day],
+            ..Default::default()
+        })
+        .collect();
+        let dates: Vec<NaiveDate> = NaiveDate::from_ymd_opt(2000, 1, 2)
+            .unwrap()
+            .iter_days()
+            .take(12)
+            .collect();
+        let names: Vec<String> = Solver {
+            seed: 42,
+            iterations: 500,
+        }
+        .assign(&people, &dates, &Constraints::default())
+        .unwrap()
+        .into_iter()
+        .map(|r| r.person.name)
+        .collect();
+        assert_eq!(names.join(""), "cbcabcabacba");
+    }
+}
@@ -1,57 +1,812 @@
 pub mod construction {
+    use crate::calendar::WorkCalendar;
+    use crate::heb_cal::apply_policy;
     use crate::heb_cal::exclude_holidays_from_file;
     use crate::heb_cal::generate_heb;
-    use crate::heb_cal::HebDateRaw;
+    use crate::heb_cal::CachePolicy;
+    use crate::heb_cal::ExclusionReport;
+    use crate::heb_cal::HebDate;
     use crate::list::*;
-    use chrono::Datelike;
-    use csv::Writer;
+    use chrono::{Datelike, NaiveDate, Weekday};
+    use csv::{Reader, Writer};
+    use rand::{seq::SliceRandom, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
     use serde::Deserialize;
     use serde::Serialize;
-    use table_configs::{config, paths};
+    use std::collections::HashMap;
+    use table_configs::{
+        config::{self, DEFAULT_TASK},
+        paths,
+    };
 
-    pub fn create_table(exclude_dates: bool) -> Result<String, Box<dyn std::error::Error>> {
+    const HISTORY_FILE: &str = "duty_history.csv";
+    const METADATA_FILE: &str = "table_metadata.json";
+
+    ///How a new table relates to the existing one.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum TableMode {
+        ///Start on the config's start_date
+        New,
+        ///Start the day after the existing table ends and carry on its rotation
+        Continue,
+        ///Append this many workdays to the existing table, keeping its rows
+        Extend(usize),
+    }
+
+    pub fn create_table(
+        exclude_dates: bool,
+        refresh_holidays: bool,
+        mode: TableMode,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let mut config = config::load_config();
+        let previous = read_rows(&paths::get_output_path(&config.output_file_name))?;
+        if mode != TableMode::New {
+            let last = previous.last().ok_or(match mode {
+                TableMode::Extend(_) => "There is no table to extend, create one first.",
+                _ => "There is no table to continue.",
+            })?;
+            config.start_date = NaiveDate::parse_from_str(&last.date, "%Y-%m-%d")?
+                .succ_opt()
+                .unwrap();
+        }
+        if let TableMode::Extend(days) = mode {
+            config.range = days;
+        }
+        let metadata_path = paths::get_output_path(METADATA_FILE);
+        let carried = (mode != TableMode::New).then_some(metadata_path.as_str());
+        let seed = shuffle_seed(&config, carried)?;
+        let mut rosters = load_rosters(&config, seed)?;
+        if mode != TableMode::New {
+            for (task, people) in config.tasks.iter().zip(rosters.iter_mut()) {
+                continue_rotation(people, &previous, &task.name);
+            }
+        }
+        let history = load_history(&config)?;
+
+        //An extended table keeps its rows, so the excluded dates cover them as well.
+        let first_year = match (mode, previous.first()) {
+            (TableMode::Extend(_), Some(first)) => {
+                NaiveDate::parse_from_str(&first.date, "%Y-%m-%d")?.year()
+            }
+            _ => config.start_date.year(),
+        };
+        let schedule = generate(
+            &config,
+            &rosters,
+            &history,
+            exclude_dates,
+            refresh_holidays,
+            first_year,
+            None,
+        )?;
+        let raws = schedule.rows.clone();
+        println!("{}", duty_report(&config.tasks, &rosters, &history, &raws));
+        let generated = read_rows(&paths::get_output_path(HISTORY_FILE))?;
+        write_csv(
+            &paths::get_output_path(HISTORY_FILE),
+            &record_generated(generated, &config.start_date, &raws),
+        )?;
+        let raws = match mode {
+            TableMode::Extend(_) => previous.into_iter().chain(raws).collect(),
+            _ => raws,
+        };
+        println!("{}", weekday_report(&config.tasks, &rosters, &raws));
+        write_tables(&config, &raws, &schedule, seed)?;
+
+        Ok(std::fs::read_to_string(paths::get_output_path(
+            &config.output_file_name,
+        ))?)
+    }
+
+    ///Regenerate the current table after `cutoff` with the current rosters, e.g. when someone joins
+    ///or leaves. With `keep_swaps` the switches made on later dates are kept where still valid.
+    pub fn regenerate_table(
+        cutoff: NaiveDate,
+        keep_swaps: bool,
+        refresh_holidays: bool,
+    ) -> Result<String, Box<dyn std::error::Error>> {
         let config = config::load_config();
-        let mut heb_cal = generate_heb()?;
-        if exclude_dates {
-            heb_cal = exclude_holidays_from_file(heb_cal, &paths::get_excluded_holidays_path())?;
+        let table = read_rows(&paths::get_output_path(&config.output_file_name))?;
+        let generated = read_rows(&paths::get_output_path(HISTORY_FILE))?;
+        let swaps = if keep_swaps {
+            find_swaps(&table, &generated)
+        } else {
+            vec![]
+        };
+        let seed = shuffle_seed(&config, Some(&paths::get_output_path(METADATA_FILE)))?;
+        let rosters = load_rosters(&config, seed)?;
+        //Duties up to the cutoff, in this table and the earlier ones
+        let history = load_history(&config::Config {
+            start_date: cutoff.succ_opt().unwrap(),
+            ..config.clone()
+        })?;
+        let (first, last) = match (table.first(), table.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Err("There is no table to regenerate.".into()),
+        };
+        let (holidays, report) = load_holidays(
+            &config,
+            true,
+            refresh_holidays,
+            NaiveDate::parse_from_str(&first.date, "%Y-%m-%d")?.year(),
+            NaiveDate::parse_from_str(&last.date, "%Y-%m-%d")?.year(),
+        )?;
+        if let Some(report) = report {
+            println!("{}", report);
         }
-        let mut people = parse_candidates_from_file(&paths::get_names_path());
+        let inputs = ScheduleInputs {
+            holidays,
+            availability: Availability::from_file(&paths::get_unavailability_path())?,
+            pairing: Pairing::from_file(&paths::get_pairing_path())?,
+        };
+        let schedule =
+            regenerate_schedule(&config, &table, &rosters, &history, cutoff, &swaps, &inputs)?;
+        print_unsatisfied(&schedule);
+
+        let cutoff_date = cutoff.format("%Y-%m-%d").to_string();
+        let future: Vec<NamesTableRaw> = schedule
+            .rows
+            .iter()
+            .filter(|r| r.date > cutoff_date)
+            .cloned()
+            .collect();
+        println!(
+            "{}",
+            duty_report(&config.tasks, &rosters, &history, &future)
+        );
+        write_csv(
+            &paths::get_output_path(HISTORY_FILE),
+            &record_generated(generated, &cutoff.succ_opt().unwrap(), &future),
+        )?;
+        println!(
+            "{}",
+            weekday_report(&config.tasks, &rosters, &schedule.rows)
+        );
+        write_tables(&config, &schedule.rows, &schedule, seed)?;
+
+        Ok(std::fs::read_to_string(paths::get_output_path(
+            &config.output_file_name,
+        ))?)
+    }
+
+    ///Keep the rows of `schedule` on or before `cutoff` untouched and schedule the dates after it,
+    ///up to the end of `schedule`, again with `rosters` (one per task, in config order) around
+    ///`inputs`. `history` has the duties done up to the cutoff, in this table and earlier ones.
+    ///`swaps` are rows of `schedule` moved to the new schedule when the person is still on the
+    ///task's roster and can take the date. Whoever is displaced takes the person's own date instead.
+    pub fn regenerate_schedule(
+        config: &config::Config,
+        schedule: &[NamesTableRaw],
+        rosters: &[Vec<Person>],
+        history: &[NamesTableRaw],
+        cutoff: NaiveDate,
+        swaps: &[NamesTableRaw],
+        inputs: &ScheduleInputs,
+    ) -> Result<Schedule, Box<dyn std::error::Error>> {
+        let cutoff_date = cutoff.format("%Y-%m-%d").to_string();
+        let (past, future): (Vec<NamesTableRaw>, Vec<NamesTableRaw>) = schedule
+            .iter()
+            .cloned()
+            .partition(|r| r.date <= cutoff_date);
+        let first = schedule.first().ok_or("There is no table to regenerate.")?;
+        let first_year = NaiveDate::parse_from_str(&first.date, "%Y-%m-%d")?.year();
+        let last = NaiveDate::parse_from_str(&schedule.last().unwrap().date, "%Y-%m-%d")?;
+
+        //The whole table is scheduled again so tasks keep their pace, only later dates are assigned
+        let mut config = config.clone();
+        config.start_date = NaiveDate::parse_from_str(&first.date, "%Y-%m-%d")?;
+        config.range = WorkCalendar::from_config(&config)
+            .workdays_from(&config.start_date)
+            .take_while(|d| *d <= last)
+            .count();
+        let mut rosters = rosters.to_vec();
+        for (task, people) in config.tasks.iter().zip(rosters.iter_mut()) {
+            continue_rotation(people, &past, &task.name);
+        }
+        let mut generated = assign_tasks(
+            &config,
+            &rosters,
+            history,
+            inputs,
+            cutoff.succ_opt(),
+            (first_year, last.year()),
+        )?;
+        let constraints = Constraints {
+            staffing: config.staffing.clone(),
+            availability: inputs.availability.clone(),
+            pairing: inputs.pairing.clone(),
+            ..Default::default()
+        };
+        let mut rows = generated.rows;
+        apply_swaps(
+            &mut rows,
+            swaps,
+            &future,
+            &config.tasks,
+            &rosters,
+            &constraints,
+        )?;
+        generated.rows = past.into_iter().chain(rows).collect();
+        Ok(generated)
+    }
+
+    ///What the dates are scheduled around besides the rosters.
+    #[derive(Debug, Clone, Default)]
+    pub struct ScheduleInputs {
+        ///Holidays before the holiday policy is applied
+        pub holidays: Vec<HebDate>,
+        pub availability: Availability,
+        pub pairing: Pairing,
+    }
+
+    ///Rows of a table and the holidays skipped while creating it.
+    pub struct Schedule {
+        pub rows: Vec<NamesTableRaw>,
+        pub holidays: Vec<HebDate>,
+        pub first_year: i32,
+        pub last_year: i32,
+        ///Soft constraints that couldn't be met, per task
+        pub unsatisfied: Vec<String>,
+    }
+
+    //Every task has its own roster and rotation
+    fn load_rosters(
+        config: &config::Config,
+        seed: Option<u64>,
+    ) -> Result<Vec<Vec<Person>>, Box<dyn std::error::Error>> {
+        let mut rosters = Vec::new();
+        for task in &config.tasks {
+            let mut people = parse_candidates_from_file(&paths::get_roster_path(&task.roster))?;
+            order_roster(&mut people, seed, &task.name);
+            rosters.push(people);
+        }
+        Ok(rosters)
+    }
+
+    //Sort the roster by name, or shuffle it with a generator seeded from `seed` and the task's
+    //name, so adding or reordering tasks doesn't change the other tasks' rotations
+    fn order_roster(people: &mut [Person], seed: Option<u64>, task: &str) {
         people.sort_by(|a, b| a.name.partial_cmp(&b.name).unwrap());
-        let dates = get_dates(&people, &heb_cal, &config.start_date, config.range);
+        if let Some(seed) = seed {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            //FNV-1a of the name, the same on every platform and release
+            let stream = task.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            });
+            rng.set_stream(stream);
+            people.shuffle(&mut rng);
+        }
+    }
 
-        //create tables to ./output/
-        //Names table to be used by program
-        let raws: Vec<NamesTableRaw> = dates
+    //Seed of the roster shuffle: the configured one, the one in `carried` (the metadata of the
+    //table that's carried on), or a new one
+    fn shuffle_seed(
+        config: &config::Config,
+        carried: Option<&str>,
+    ) -> Result<Option<u64>, Box<dyn std::error::Error>> {
+        if !config.shuffle {
+            return Ok(None);
+        }
+        if config.seed.is_some() {
+            return Ok(config.seed);
+        }
+        if let Some(path) = carried.filter(|path| std::path::Path::new(path).exists()) {
+            let metadata: TableMetadata = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+            if metadata.seed.is_some() {
+                return Ok(metadata.seed);
+            }
+        }
+        Ok(Some(rand::random()))
+    }
+
+    fn write_metadata(path: &str, seed: Option<u64>) -> Result<(), Box<dyn std::error::Error>> {
+        let metadata = TableMetadata {
+            created: chrono::Local::now().to_rfc3339(),
+            shuffle: seed.is_some(),
+            seed,
+        };
+        std::fs::write(path, serde_json::to_string_pretty(&metadata)?)?;
+        Ok(())
+    }
+
+    fn generate(
+        config: &config::Config,
+        rosters: &[Vec<Person>],
+        history: &[NamesTableRaw],
+        exclude_dates: bool,
+        refresh_holidays: bool,
+        first_year: i32,
+        first_date: Option<NaiveDate>,
+    ) -> Result<Schedule, Box<dyn std::error::Error>> {
+        let calendar = WorkCalendar::from_config(config);
+        let mut inputs = ScheduleInputs {
+            holidays: vec![],
+            availability: Availability::from_file(&paths::get_unavailability_path())?,
+            pairing: Pairing::from_file(&paths::get_pairing_path())?,
+        };
+
+        //Holidays push the last date forward, so years are added until the fetched ones cover it.
+        let mut last_year = calendar
+            .workdays_from(&config.start_date)
+            .take(config.range)
+            .last()
+            .unwrap_or(config.start_date)
+            .year();
+        loop {
+            let (holidays, report) = load_holidays(
+                config,
+                exclude_dates,
+                refresh_holidays,
+                first_year,
+                last_year,
+            )?;
+            inputs.holidays = holidays;
+            let schedule = assign_tasks(
+                config,
+                rosters,
+                history,
+                &inputs,
+                first_date,
+                (first_year, last_year),
+            )?;
+            let last = match schedule.rows.last() {
+                Some(last) => NaiveDate::parse_from_str(&last.date, "%Y-%m-%d")?.year(),
+                None => last_year,
+            };
+            if last <= last_year {
+                if let Some(report) = report {
+                    println!("{}", report);
+                }
+                print_unsatisfied(&schedule);
+                return Ok(schedule);
+            }
+            last_year = last;
+        }
+    }
+
+    //Holidays from the configured sources between the start of `first_year` and the end of
+    //`last_year`, without the ones in excluded_hebcal.csv when `exclude_dates` is set
+    fn load_holidays(
+        config: &config::Config,
+        exclude_dates: bool,
+        refresh_holidays: bool,
+        first_year: i32,
+        last_year: i32,
+    ) -> Result<(Vec<HebDate>, Option<ExclusionReport>), Box<dyn std::error::Error>> {
+        let cache_policy = CachePolicy {
+            path: paths::get_hebdate_path(),
+            max_age_days: config.holiday_cache_days,
+            refresh: refresh_holidays,
+        };
+        let heb_cal = generate_heb(
+            &config.holiday_sources,
+            &cache_policy,
+            &NaiveDate::from_ymd_opt(first_year, 1, 1).unwrap(),
+            &NaiveDate::from_ymd_opt(last_year, 12, 31).unwrap(),
+        )?;
+        if !exclude_dates {
+            return Ok((heb_cal, None));
+        }
+        let (heb_cal, report) =
+            exclude_holidays_from_file(heb_cal, &paths::get_excluded_holidays_path())?;
+        Ok((heb_cal, Some(report)))
+    }
+
+    //Every task's dates scheduled around `inputs`. `years` are the years the holidays cover.
+    fn assign_tasks(
+        config: &config::Config,
+        rosters: &[Vec<Person>],
+        history: &[NamesTableRaw],
+        inputs: &ScheduleInputs,
+        first_date: Option<NaiveDate>,
+        years: (i32, i32),
+    ) -> Result<Schedule, Box<dyn std::error::Error>> {
+        let calendar = WorkCalendar::from_config(config);
+        let scheduler = scheduler_for(&config.scheduler);
+        let (heb_cal, short_days) = apply_policy(inputs.holidays.clone(), &config.holiday_policy);
+        let mut dates = Vec::new();
+        let mut unsatisfied = Vec::new();
+        for (task, people) in config.tasks.iter().zip(rosters) {
+            let assignment = schedule_dates(
+                scheduler.as_ref(),
+                people,
+                &heb_cal,
+                &config.start_date,
+                config.range,
+                &calendar,
+                &Constraints {
+                    staffing: config.staffing.clone(),
+                    frequency: task.frequency,
+                    availability: inputs.availability.clone(),
+                    history: count_duties(history, &task.name),
+                    rotate_weekdays: config.rotate_weekdays,
+                    weekday_history: count_weekdays(history, &task.name),
+                    pairing: inputs.pairing.clone(),
+                    rotate_pairs: config.rotate_pairs,
+                    first_date,
+                },
+            )
+            .map_err(|e| format!("Could not schedule {}. {}", task.name, e))?;
+            if !assignment.unsatisfied.is_empty() {
+                unsatisfied.push(format!(
+                    "{}:\n  {}",
+                    task.name,
+                    assignment.unsatisfied.join("\n  ")
+                ));
+            }
+            let mut task_dates = assignment.rows;
+            for row in task_dates.iter_mut() {
+                row.short_day = short_days.contains(&row.date);
+                row.task = task.name.clone();
+            }
+            dates.append(&mut task_dates);
+        }
+        //Combined schedule, tasks of the same date stay in config order
+        dates.sort_by_key(|row| row.date);
+
+        let rows = dates
             .iter()
             .map(|x| NamesTableRaw {
                 date: x.date.to_string(),
                 name: x.person.name.clone(),
                 number: x.person.phone.clone(),
+                short_day: x.short_day,
+                task: x.task.clone(),
             })
             .collect();
-        write_csv(&paths::get_output_path(&config.output_file_name), &raws)?;
+        Ok(Schedule {
+            rows,
+            holidays: heb_cal,
+            first_year: years.0,
+            last_year: years.1,
+            unsatisfied,
+        })
+    }
+
+    fn print_unsatisfied(schedule: &Schedule) {
+        if !schedule.unsatisfied.is_empty() {
+            println!(
+                "Soft constraints not met:\n{}",
+                schedule.unsatisfied.join("\n")
+            );
+        }
+    }
+
+    //create tables to ./output/
+    fn write_tables(
+        config: &config::Config,
+        raws: &Vec<NamesTableRaw>,
+        schedule: &Schedule,
+        seed: Option<u64>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        //Names table to be used by program
+        write_csv(&paths::get_output_path(&config.output_file_name), raws)?;
+        write_metadata(&paths::get_output_path(METADATA_FILE), seed)?;
 
         //Names table created for end-user use
-        let raws_beaut: Vec<BeautyNameTableRaw> = dates
+        let mut raws_beaut: Vec<BeautyNameTableRaw> = Vec::new();
+        for x in raws.iter() {
+            //Everyone else on the same date and task
+            let with: Vec<&str> = raws
+                .iter()
+                .filter(|r| r.date == x.date && r.task == x.task && r.name != x.name)
+                .map(|r| r.name.as_str())
+                .collect();
+            raws_beaut.push(BeautyNameTableRaw {
+                date: x.date.clone(),
+                day: NaiveDate::parse_from_str(&x.date, "%Y-%m-%d")?
+                    .weekday()
+                    .to_string(),
+                task: x.task.clone(),
+                name: x.name.clone(),
+                with: with.join(" & "),
+                short_day: if x.short_day {
+                    "short day".to_string()
+                } else {
+                    String::new()
+                },
+            });
+        }
+        write_csv(&paths::get_output_path("beautified_table.csv"), &raws_beaut)?;
+        //final excluded dates to be used by program as well
+        let covered_years = format!("{}-{}", schedule.first_year, schedule.last_year);
+        let final_excluded_dates: Vec<ExcludedDateRaw> = schedule
+            .holidays
             .iter()
-            .map(|x| BeautyNameTableRaw {
-                date: x.date.to_string(),
-                day: x.date.weekday().to_string(),
-                name: x.person.name.clone(),
+            .map(|x| ExcludedDateRaw {
+                title: x.title.clone(),
+                date: x.date.format("%Y-%m-%d").to_string(),
+                covered_years: covered_years.clone(),
             })
             .collect();
-        write_csv(&paths::get_output_path("beautified_table.csv"), &raws_beaut)?;
-        //final excluded dates to be used by program as well
-        let final_excluded_dates: Vec<HebDateRaw> =
-            heb_cal.iter().map(|x| HebDateRaw::from(x)).collect();
         write_csv(
             &paths::get_output_path("excluded_dates.csv"),
             &final_excluded_dates,
-        )?;
+        )
+    }
 
-        Ok(std::fs::read_to_string(&paths::get_output_path(
-            &config.output_file_name,
-        ))?)
+    //Position of every row among the rows of its date and task
+    fn slots(rows: &[NamesTableRaw]) -> Vec<((String, String, usize), &NamesTableRaw)> {
+        let mut counts: HashMap<(&str, &str), usize> = HashMap::new();
+        rows.iter()
+            .map(|r| {
+                let slot = counts.entry((&r.date, &r.task)).or_insert(0);
+                *slot += 1;
+                ((r.date.clone(), r.task.clone(), *slot - 1), r)
+            })
+            .collect()
+    }
+
+    ///Rows of `table` that were switched with another row since the table was `generated`.
+    pub fn find_swaps(table: &[NamesTableRaw], generated: &[NamesTableRaw]) -> Vec<NamesTableRaw> {
+        let generated: HashMap<(String, String, usize), &str> = slots(generated)
+            .into_iter()
+            .map(|(slot, r)| (slot, r.name.as_str()))
+            .collect();
+        let table = slots(table);
+        let changed: Vec<(&str, &str, &NamesTableRaw)> = table
+            .iter()
+            .filter_map(|(slot, r)| {
+                let before = *generated.get(slot)?;
+                (before != r.name).then_some((r.name.as_str(), before, *r))
+            })
+            .collect();
+        //A switch exchanges two people, anything else (drops, collapses) isn't kept
+        changed
+            .iter()
+            .filter(|(now, before, row)| {
+                changed.iter().any(|(other_now, other_before, other)| {
+                    other_now == before && other_before == now && other.task == row.task
+                })
+            })
+            .map(|(_, _, row)| (*row).clone())
+            .collect()
+    }
+
+    //Put swapped people back on their dates when they are still on the task's roster and the
+    //date is still scheduled. Whoever is displaced takes the person's nearest regenerated date of
+    //the task, so the switch doesn't change anyone's count. Switches someone can't take are dropped.
+    fn apply_swaps(
+        rows: &mut [NamesTableRaw],
+        swaps: &[NamesTableRaw],
+        previous: &[NamesTableRaw],
+        tasks: &[config::Task],
+        rosters: &[Vec<Person>],
+        constraints: &Constraints,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let previous_slots: HashMap<(String, String, String), usize> = slots(previous)
+            .into_iter()
+            .map(|((date, task, slot), r)| ((date, task, r.name.clone()), slot))
+            .collect();
+        for swap in swaps {
+            let roster = match tasks.iter().position(|t| t.name == swap.task) {
+                Some(i) => &rosters[i],
+                None => continue,
+            };
+            let person = match roster.iter().find(|p| p.name == swap.name) {
+                Some(person) => person,
+                None => continue,
+            };
+            let day: Vec<usize> = (0..rows.len())
+                .filter(|i| rows[*i].date == swap.date && rows[*i].task == swap.task)
+                .collect();
+            if day.iter().any(|i| rows[*i].name == person.name) {
+                continue;
+            }
+            let slot =
+                previous_slots.get(&(swap.date.clone(), swap.task.clone(), swap.name.clone()));
+            let target = match slot.and_then(|slot| day.get(*slot)) {
+                Some(target) => *target,
+                None => continue,
+            };
+            let date = NaiveDate::parse_from_str(&swap.date, "%Y-%m-%d")?;
+            let mut own = vec![];
+            for (i, r) in rows.iter().enumerate() {
+                if r.task == swap.task && r.name == person.name {
+                    let own_date = NaiveDate::parse_from_str(&r.date, "%Y-%m-%d")?;
+                    own.push((i, own_date));
+                }
+            }
+            let (own, own_date) = match own
+                .into_iter()
+                .min_by_key(|(_, own_date)| (*own_date - date).num_days().abs())
+            {
+                Some(own) => own,
+                None => continue,
+            };
+            let displaced = match roster.iter().find(|p| p.name == rows[target].name) {
+                Some(displaced) => displaced,
+                None => continue,
+            };
+            let person_conflict = conflict(
+                roster,
+                &person.name,
+                &date,
+                &others_on(rows, target),
+                constraints,
+            );
+            let displaced_conflict = conflict(
+                roster,
+                &displaced.name,
+                &own_date,
+                &others_on(rows, own),
+                constraints,
+            );
+            if person_conflict.is_some() || displaced_conflict.is_some() {
+                continue;
+            }
+            rows[target].name = person.name.clone();
+            rows[target].number = person.phone.clone();
+            rows[own].name = displaced.name.clone();
+            rows[own].number = displaced.phone.clone();
+        }
+        Ok(())
+    }
+
+    //Everyone else on the date and task of `rows[index]`
+    fn others_on(rows: &[NamesTableRaw], index: usize) -> Vec<&str> {
+        rows.iter()
+            .enumerate()
+            .filter(|(i, r)| {
+                *i != index && r.date == rows[index].date && r.task == rows[index].task
+            })
+            .map(|(_, r)| r.name.as_str())
+            .collect()
+    }
+
+    //Start the rotation with the person after the last one the previous table assigned to the task
+    fn continue_rotation(people: &mut [Person], previous: &[NamesTableRaw], task: &str) {
+        let last = previous.iter().rev().find(|r| r.task == task);
+        if let Some(position) =
+            last.and_then(|last| people.iter().position(|p| p.name == last.name))
+        {
+            people.rotate_left(position + 1);
+        }
+    }
+
+    //duty_history.csv keeps the rows as they were generated, without the switches made in the
+    //table since, so later swaps can still be told apart. Rows from `start` on are replaced.
+    fn record_generated(
+        mut generated: Vec<NamesTableRaw>,
+        start: &NaiveDate,
+        rows: &[NamesTableRaw],
+    ) -> Vec<NamesTableRaw> {
+        let start = start.format("%Y-%m-%d").to_string();
+        generated.retain(|r| r.date < start);
+        generated.extend(rows.iter().cloned());
+        generated
+    }
+
+    //Duties of earlier tables, read from duty_history.csv and the previous table, which has the
+    //switches and drops made since it was created. Duties from the new start date on are replaced.
+    fn load_history(
+        config: &config::Config,
+    ) -> Result<Vec<NamesTableRaw>, Box<dyn std::error::Error>> {
+        let mut history = read_rows(&paths::get_output_path(HISTORY_FILE))?;
+        let table = read_rows(&paths::get_output_path(&config.output_file_name))?;
+        if let (Some(first), Some(last)) = (table.first(), table.last()) {
+            let (first, last) = (first.date.clone(), last.date.clone());
+            history.retain(|r| r.date < first || r.date > last);
+        }
+        history.extend(table);
+        let start_date = config.start_date.format("%Y-%m-%d").to_string();
+        history.retain(|r| r.date < start_date);
+        history.sort_by(|a, b| a.date.cmp(&b.date));
+        Ok(history)
+    }
+
+    fn read_rows(path: &str) -> Result<Vec<NamesTableRaw>, Box<dyn std::error::Error>> {
+        if !std::path::Path::new(path).exists() {
+            return Ok(vec![]);
+        }
+        let mut rows = vec![];
+        for row in Reader::from_path(path)?.deserialize() {
+            let mut row: NamesTableRaw = row?;
+            row.date = NaiveDate::parse_from_str(row.date.trim(), "%Y-%m-%d")?
+                .format("%Y-%m-%d")
+                .to_string();
+            if row.task.is_empty() {
+                row.task = DEFAULT_TASK.to_string();
+            }
+            rows.push(row);
+        }
+        rows.sort_by(|a, b| a.date.cmp(&b.date));
+        Ok(rows)
+    }
+
+    fn count_duties(rows: &[NamesTableRaw], task: &str) -> HashMap<String, usize> {
+        let mut duties = HashMap::new();
+        for row in rows.iter().filter(|r| r.task == task) {
+            *duties.entry(row.name.clone()).or_insert(0) += 1;
+        }
+        duties
+    }
+
+    //Earlier, new and total duties of everyone on a task's roster
+    fn duty_report(
+        tasks: &[config::Task],
+        rosters: &[Vec<Person>],
+        history: &[NamesTableRaw],
+        table: &[NamesTableRaw],
+    ) -> String {
+        let mut report = String::from("Duties per person (earlier + new = total):");
+        for (task, people) in tasks.iter().zip(rosters) {
+            let before = count_duties(history, &task.name);
+            let new = count_duties(table, &task.name);
+            report.push_str(&format!("\n{}:", task.name));
+            for person in people {
+                let before = before.get(&person.name).cloned().unwrap_or(0);
+                let new = new.get(&person.name).cloned().unwrap_or(0);
+                report.push_str(&format!(
+                    "\n  {}: {} + {} = {}",
+                    person.name,
+                    before,
+                    new,
+                    before + new
+                ));
+            }
+        }
+        report
+    }
+
+    const WEEKDAYS: [Weekday; 7] = [
+        Weekday::Sun,
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+        Weekday::Sat,
+    ];
+
+    fn count_weekdays(
+        rows: &[NamesTableRaw],
+        task: &str,
+    ) -> HashMap<String, HashMap<Weekday, usize>> {
+        let mut weekdays: HashMap<String, HashMap<Weekday, usize>> = HashMap::new();
+        for row in rows.iter().filter(|r| r.task == task) {
+            if let Ok(date) = NaiveDate::parse_from_str(&row.date, "%Y-%m-%d") {
+                *weekdays
+                    .entry(row.name.clone())
+                    .or_default()
+                    .entry(date.weekday())
+                    .or_insert(0) += 1;
+            }
+        }
+        weekdays
+    }
+
+    //Duties of everyone on a task's roster on every weekday of the table
+    fn weekday_report(
+        tasks: &[config::Task],
+        rosters: &[Vec<Person>],
+        table: &[NamesTableRaw],
+    ) -> String {
+        let mut report = String::from("Duties per weekday:");
+        for (task, people) in tasks.iter().zip(rosters) {
+            let weekdays = count_weekdays(table, &task.name);
+            //Only the weekdays the task is scheduled on
+            let days: Vec<Weekday> = WEEKDAYS
+                .into_iter()
+                .filter(|day| weekdays.values().any(|counts| counts.contains_key(day)))
+                .collect();
+            report.push_str(&format!("\n{}:", task.name));
+            for person in people {
+                let counts: Vec<String> = days
+                    .iter()
+                    .map(|day| {
+                        let count = weekdays
+                            .get(&person.name)
+                            .and_then(|counts| counts.get(day))
+                            .cloned()
+                            .unwrap_or(0);
+                        format!("{} {}", day, count)
+                    })
+                    .collect();
+                report.push_str(&format!("\n  {}: {}", person.name, counts.join(", ")));
+            }
+        }
+        report
     }
 
     fn write_csv<T>(file_path: &str, t: &Vec<T>) -> Result<(), Box<dyn std::error::Error>>
@@ -66,18 +821,296 @@ pub mod construction {
         Ok(())
     }
 
-    #[derive(Serialize, Deserialize)]
+    #[derive(Serialize, Deserialize, Clone)]
     pub struct NamesTableRaw {
         pub name: String,
         pub number: String,
         pub date: String,
+        #[serde(default)]
+        pub short_day: bool,
+        ///Empty in tables created before tasks were added
+        #[serde(default)]
+        pub task: String,
+    }
+
+    ///How the table was created. Setting the seed in config.json creates the same rotation again.
+    #[derive(Serialize, Deserialize)]
+    pub struct TableMetadata {
+        pub created: String,
+        pub shuffle: bool,
+        #[serde(default)]
+        pub seed: Option<u64>,
+    }
+
+    ///Row of excluded_dates.csv. Every row records the span of years holidays were fetched for.
+    #[derive(Serialize, Deserialize)]
+    pub struct ExcludedDateRaw {
+        pub title: String,
+        pub date: String,
+        pub covered_years: String,
     }
 
     #[derive(Serialize, Deserialize)]
     pub struct BeautyNameTableRaw {
         pub day: String,
         pub date: String,
+        pub task: String,
         pub name: String,
+        pub with: String,
+        pub short_day: String,
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn rows(rows: &[(&str, &str)]) -> Vec<NamesTableRaw> {
+            rows.iter()
+                .map(|(date, name)| NamesTableRaw {
+                    name: name.to_string(),
+                    number: "0".to_string(),
+                    date: date.to_string(),
+                    short_day: false,
+                    task: DEFAULT_TASK.to_string(),
+                })
+                .collect()
+        }
+
+        #[test]
+        fn swaps_are_kept() {
+            let generated = rows(&[
+                ("2022-05-15", "a"),
+                ("2022-05-16", "b"),
+                ("2022-05-17", "c"),
+                ("2022-05-18", "a"),
+            ]);
+            //b and c switched, the last date was dropped with collapse
+            let table = rows(&[
+                ("2022-05-15", "a"),
+                ("2022-05-16", "c"),
+                ("2022-05-17", "b"),
+                ("2022-05-18", "d"),
+            ]);
+            let swaps = find_swaps(&table, &generated);
+            let swapped: Vec<&str> = swaps.iter().map(|r| r.name.as_str()).collect();
+            assert_eq!(swapped, vec!["c", "b"]);
+
+            //c left, so only b keeps the switched date and y, whom b displaces, takes b's date
+            let roster: Vec<Person> = ["b", "x", "y"]
+                .iter()
+                .map(|name| Person {
+                    name: name.to_string(),
+                    phone: "0".to_string(),
+                    ..Default::default()
+                })
+                .collect();
+            let regenerated = rows(&[
+                ("2022-05-16", "x"),
+                ("2022-05-17", "y"),
+                ("2022-05-18", "b"),
+            ]);
+            let apply = |constraints: &Constraints| {
+                let mut regenerated = regenerated.clone();
+                apply_swaps(
+                    &mut regenerated,
+                    &swaps,
+                    &table,
+                    &[config::Task::default()],
+                    std::slice::from_ref(&roster),
+                    constraints,
+                )
+                .unwrap();
+                regenerated
+                    .iter()
+                    .map(|r| r.name.clone())
+                    .collect::<Vec<String>>()
+            };
+            assert_eq!(apply(&Constraints::default()), vec!["x", "b", "y"]);
+
+            //b can't make the switched date anymore
+            let constraints = Constraints {
+                availability: Availability::parse("name,from,to,weekdays\nb,2022-05-17,,\n")
+                    .unwrap(),
+                ..Default::default()
+            };
+            assert_eq!(apply(&constraints), vec!["x", "y", "b"]);
+        }
+
+        fn test_config() -> config::Config {
+            config::Config::from(
+                serde_json::from_str(
+                    r#"{
+                "start_date": "2022-05-15",
+                "range": 6,
+                "output_file_name":"test",
+                "send_time":"09:00:00",
+                "reset_time":"01:00:00",
+                "maintainer":"",
+                "alert_day":5,
+                "weekend":[6,7],
+                "key":"",
+                "user":"",
+                "pass":"",
+                "sender":""
+                }"#,
+                )
+                .unwrap(),
+            )
+        }
+
+        fn people(names: &[&str]) -> Vec<Person> {
+            names
+                .iter()
+                .map(|name| Person {
+                    name: name.to_string(),
+                    phone: "0".to_string(),
+                    ..Default::default()
+                })
+                .collect()
+        }
+
+        fn order(seed: Option<u64>, task: &str) -> String {
+            let mut roster = people(&["h", "g", "f", "e", "d", "c", "b", "a"]);
+            order_roster(&mut roster, seed, task);
+            roster.iter().map(|p| p.name.as_str()).collect()
+        }
+
+        #[test]
+        fn shuffle_is_reproducible() {
+            assert_eq!(order(None, "kitchen"), "abcdefgh");
+            assert_eq!(order(Some(7), "kitchen"), order(Some(7), "kitchen"));
+            assert_ne!(order(Some(7), "kitchen"), order(Some(8), "kitchen"));
+            //Every task has its own order
+            assert_ne!(order(Some(7), "kitchen"), order(Some(7), "trash"));
+            //ChaCha8 is portable, so the order is pinned
+            assert_eq!(order(Some(7), "kitchen"), "hdegcabf");
+        }
+
+        #[test]
+        fn seed_is_carried_on() {
+            let path = "./test_table_metadata.json";
+            write_metadata(path, Some(5)).unwrap();
+            let metadata: TableMetadata =
+                serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+            assert!(metadata.shuffle);
+            assert_eq!(metadata.seed, Some(5));
+
+            let mut config = test_config();
+            config.shuffle = true;
+            //--continue, --extend and --regenerate read the current table's metadata
+            let carried = shuffle_seed(&config, Some(path));
+            let new = shuffle_seed(&config, None);
+            config.seed = Some(3);
+            let configured = shuffle_seed(&config, Some(path));
+            config.shuffle = false;
+            let unshuffled = shuffle_seed(&config, Some(path));
+            write_metadata(path, None).unwrap();
+            let metadata: TableMetadata =
+                serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+            std::fs::remove_file(path).unwrap();
+
+            assert_eq!(carried.unwrap(), Some(5));
+            assert!(new.unwrap().is_some());
+            assert_eq!(configured.unwrap(), Some(3));
+            assert_eq!(unshuffled.unwrap(), None);
+            assert!(!metadata.shuffle);
+            assert_eq!(metadata.seed, None);
+        }
+
+        #[test]
+        fn swaps_survive_extend() {
+            let names = |rows: &[NamesTableRaw]| -> Vec<String> {
+                rows.iter().map(|r| r.name.clone()).collect()
+            };
+            let generated = rows(&[
+                ("2022-05-15", "a"),
+                ("2022-05-16", "b"),
+                ("2022-05-17", "c"),
+            ]);
+            //b and c switched, then --extend added two dates
+            let mut table = rows(&[
+                ("2022-05-15", "a"),
+                ("2022-05-16", "c"),
+                ("2022-05-17", "b"),
+            ]);
+            let extension = rows(&[("2022-05-18", "a"), ("2022-05-19", "b")]);
+            let start = NaiveDate::from_ymd_opt(2022, 5, 18).unwrap();
+            let generated = record_generated(generated, &start, &extension);
+            table.extend(extension);
+            assert_eq!(names(&generated), vec!["a", "b", "c", "a", "b"]);
+            let swaps = find_swaps(&table, &generated);
+            assert_eq!(names(&swaps), vec!["c", "b"]);
+
+            //--regenerate --keep-swaps after the first date
+            let regenerate = |swaps: &[NamesTableRaw]| {
+                let schedule = regenerate_schedule(
+                    &test_config(),
+                    &table,
+                    &[people(&["a", "b", "c"])],
+                    &table[..1],
+                    NaiveDate::from_ymd_opt(2022, 5, 15).unwrap(),
+                    swaps,
+                    &ScheduleInputs::default(),
+                )
+                .unwrap();
+                names(&schedule.rows)
+            };
+            assert_eq!(regenerate(&swaps)[1..3], ["c", "b"]);
+            assert_eq!(regenerate(&[])[1..3], ["b", "c"]);
+        }
+
+        fn to_csv(rows: &[NamesTableRaw]) -> String {
+            let mut writer = Writer::from_writer(vec![]);
+            for row in rows {
+                writer.serialize(row).unwrap();
+            }
+            String::from_utf8(writer.into_inner().unwrap()).unwrap()
+        }
+
+        #[test]
+        fn rows_before_cutoff_are_kept() {
+            let config = test_config();
+            //Edited rows: a changed number, someone who left and a short day
+            let mut table = rows(&[
+                ("2022-05-15", "a"),
+                ("2022-05-16", "gone"),
+                ("2022-05-17", "c"),
+                ("2022-05-18", "a"),
+                ("2022-05-19", "b"),
+                ("2022-05-22", "c"),
+            ]);
+            table[0].number = " 050-1 ".to_string();
+            table[1].short_day = true;
+            let roster: Vec<Person> = ["a", "b", "new"]
+                .iter()
+                .map(|name| Person {
+                    name: name.to_string(),
+                    phone: "1".to_string(),
+                    ..Default::default()
+                })
+                .collect();
+            let cutoff = NaiveDate::from_ymd_opt(2022, 5, 16).unwrap();
+            let schedule = regenerate_schedule(
+                &config,
+                &table,
+                &[roster],
+                &table[..2],
+                cutoff,
+                &[],
+                &ScheduleInputs::default(),
+            )
+            .unwrap();
+
+            assert_eq!(to_csv(&schedule.rows[..2]), to_csv(&table[..2]));
+            let dates: Vec<&str> = schedule.rows[2..].iter().map(|r| r.date.as_str()).collect();
+            assert_eq!(
+                dates,
+                vec!["2022-05-17", "2022-05-18", "2022-05-19", "2022-05-22"]
+            );
+            assert!(schedule.rows[2..]
+                .iter()
+                .all(|r| r.name != "gone" && r.name != "c"));
+        }
     }
 }
 
@@ -57,39 +57,59 @@ fn show(params:&[&str],tx_request_from_main:&Sender<Request>,rx_output:&Receiver
 }
 
 fn switch(params:&[&str],tx_request_from_main:&Sender<Request>,rx_output:&Receiver<Vec<Box<dyn Display + Send>>>)->Result<Vec<Box<dyn Display + Send>>,Box<dyn std::error::Error>>{
+            let (task, params) = split_task(params);
             if params.len() != 2 {
                 Ok(vec![Box::new("Incorrect number of parameters".to_string())])
             } else {
-                if let Ok(date1) = NaiveDate::parse_from_str(params[0], "%Y-%m-%d") {
-                    if let Ok(date2) = NaiveDate::parse_from_str(params[1], "%Y-%m-%d")
+                if let Some(first) = parse_assignee(params[0]) {
+                    if let Some(second) = parse_assignee(params[1])
                     {
-                        tx_request_from_main.send(Request::Switch(date1, date2))?;
+                        tx_request_from_main.send(Request::Switch(task, first, second))?;
                         Ok(rx_output.recv()?)
                     } else {
-                        Ok(vec![Box::new("Second date could not be parsed. Expecting YYYY-mm-dd".to_string())])
+                        Ok(vec![Box::new("Second date could not be parsed. Expecting YYYY-mm-dd or YYYY-mm-dd/NAME".to_string())])
                     }
                 } else {
-                    Ok(vec![Box::new("First date could not be parsed. Expecting YYYY-mm-dd".to_string())])
+                    Ok(vec![Box::new("First date could not be parsed. Expecting YYYY-mm-dd or YYYY-mm-dd/NAME".to_string())])
                 }
             }
 }
 
+//An optional task name comes before the first date
+fn split_task<'a>(params:&'a [&'a str])->(Option<String>,&'a [&'a str]){
+    match params.first(){
+        Some(first) if parse_assignee(first).is_none()=>(Some(first.to_string()),&params[1..]),
+        _=>(None,params),
+    }
+}
+
+//Parse "YYYY-mm-dd" or "YYYY-mm-dd/NAME"
+fn parse_assignee(param:&str)->Option<(NaiveDate,Option<String>)>{
+    let (date, name) = match param.split_once('/'){
+        Some((date, name))=>(date, Some(name.to_string())),
+        None=>(param, None),
+    };
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok().map(|date| (date, name))
+}
+
 fn drop(params:&[&str],tx_request_from_main:&Sender<Request>,rx_output:&Receiver<Vec<Box<dyn Display + Send>>>)->Result<Vec<Box<dyn Display + Send>>,Box<dyn std::error::Error>>{
-            if params.len() == 2 {
-                let action = params[0];
-                let date = params[1];
+            let action = params.first().cloned().unwrap_or_default();
+            let (task, params) = split_task(params.get(1..).unwrap_or_default());
+            if params.len() == 1 || params.len() == 2 {
+                let date = params[0];
+                let name = params.get(1).map(|name| name.to_string());
                 if let Ok(date) = NaiveDate::parse_from_str(date, "%Y-%m-%d") {
                     match action {
                         "postpone" => {
-                            tx_request_from_main.send(Request::Drop(DropType::Postpone, date))?;
+                            tx_request_from_main.send(Request::Drop(DropType::Postpone, task, date, name))?;
                             Ok(rx_output.recv()?)
                         }
                         "collapse" => {
-                            tx_request_from_main.send(Request::Drop(DropType::Collapse, date))?;
+                            tx_request_from_main.send(Request::Drop(DropType::Collapse, task, date, name))?;
                             Ok(rx_output.recv()?)
                         }
                         "clean" => {
-                            tx_request_from_main.send(Request::Drop(DropType::Clean, date))?;
+                            tx_request_from_main.send(Request::Drop(DropType::Clean, task, date, name))?;
                             Ok(rx_output.recv()?)
                         }
                         _ => Ok(
@@ -108,13 +128,22 @@ fn get_help()->String{
     r#"Options:
 status                                      - Prints current status.
 show NUMBER                                 - Show current and NUMBER of following weeks.
-switch YYYY-mm-dd YYYY-mm-dd                - Switch between two given dates and update the original table.
-drop [clean|collapse|postpone] YYYY-mm-dd   - Remove a date. 
+switch [TASK] YYYY-mm-dd[/NAME] YYYY-mm-dd[/NAME]
+                                            - Switch between two given dates and update the original table.
+                                                With NAME only that assignee of the date is switched.
+                                                TASK picks the task's table, it's required when there
+                                                are several tasks.
+drop [clean|collapse|postpone] [TASK] YYYY-mm-dd [NAME]
+                                            - Remove a date. 
                                                 Clean    - Simply remove the date.
                                                 Collapse - Replace given date's name with the next date's one. 
                                                            repeat for every following date.
                                                 Postpone - Move given date's name one day forward and repeat
                                                            for every following name.
+                                                With NAME only that assignee is removed, and only the people
+                                                in the same position on the following dates are moved.
+                                                TASK picks the task's table, it's required when there
+                                                are several tasks.
 resend                                      - Send the message again disregarding built-in limitation.
 help                                        - Display this text."#.to_string()
 }
\ No newline at end of file
@@ -2,19 +2,22 @@ mod reader;
 mod sender;
 mod cli;
 
-use std::{collections::HashMap, process::exit, thread, fmt::Display};
+use std::{collections::{HashMap, HashSet}, process::exit, thread, fmt::Display};
 
 use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
 use colored::Colorize;
-use reader::table::get_people_table;
-use sender::send_to;
+use reader::table::{add_contacts, get_people_table, get_short_days, TaskTables};
+use sender::{transport_for, DryRun, MessageTransport, Reminder};
 use std::sync::mpsc;
-use table_configs::{config::{self, Config}, paths};
-use table_maker::Person;
+use table_configs::{config::{self, Config, DEFAULT_TASK}, paths};
+use table_maker::{HebDate, Person, WorkCalendar};
 
-const MESSAGE: &str = "תזכורת ניקיון\nבמקרה בו אינך יכול/ה לנקות הודיעו לאחראים";
+const MESSAGE: &str = "תזכורת ניקיון";
+const MESSAGE_NOTE: &str = "במקרה בו אינך יכול/ה לנקות הודיעו לאחראים";
+const SHORT_DAY_MESSAGE: &str = "(יום קצר)";
 
-pub fn start_interface() -> Result<(), Box<dyn std::error::Error>> {
+//A dry run logs the messages instead of sending them and otherwise runs as usual
+pub fn start_interface(dry_run: bool) -> Result<(), Box<dyn std::error::Error>> {
     let config = config::load_config();
 
     if !std::path::Path::new(&table_configs::paths::get_output_path(
@@ -27,14 +30,22 @@ pub fn start_interface() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     let thread_config = config.clone();
-    let table = get_people_table(&paths::get_output_path(&config.output_file_name))?;
+    let transport: Box<dyn MessageTransport> = match dry_run {
+        true => Box::new(DryRun {
+            log: paths::get_dry_run_log_path(),
+        }),
+        false => transport_for(&config),
+    };
+    let mut table = get_people_table(&paths::get_output_path(&config.output_file_name))?;
+    add_contacts(&mut table, &config);
+    let short_days = get_short_days(&paths::get_output_path(&config.output_file_name))?;
     let (tx_request_from_main, rx_request) = mpsc::channel();
     let (tx_status, rx_status) = mpsc::channel();
     let rx_request_clock = tx_request_from_main.clone();
 
     //run the thread responsible for reading data and sending messages
     let _logic_thread =
-        thread::spawn(move || action_loop(tx_status, rx_request, &thread_config, table));
+        thread::spawn(move || action_loop(tx_status, rx_request, &thread_config, transport.as_ref(), table, short_days));
 
     //Run the thread to tick the logic_thread every set period of time
     let _clock_thread = thread::spawn(move || loop {
@@ -51,10 +62,12 @@ fn action_loop(
     transmitting: mpsc::Sender<Vec<Box<dyn Display + Send>>>,
     receiving: mpsc::Receiver<Request>,
     config: &config::Config,
-    people_table: HashMap<NaiveDate, Person>,
+    transport: &dyn MessageTransport,
+    task_tables: TaskTables,
+    short_days: HashSet<NaiveDate>,
 ) {
     let output_path = &paths::get_output_path(&config.output_file_name);
-    let mut people_table = people_table;
+    let mut task_tables = task_tables;
     let mut is_sent = false;
     let mut status = String::new();
     let mut resend = false;
@@ -74,8 +87,8 @@ send time: {}
 reset time: {}",
                                         is_sent,
                                         status.to_string(),
-                                        get_name_from_table(&people_table, 0),
-                                        get_name_from_table(&people_table, 1),
+                                        describe_day(&task_tables, 0),
+                                        describe_day(&task_tables, 1),
                                         chrono::Local::now(),
                                         config.send_time,
                                         config.send_time > config.reset_time))]
@@ -85,8 +98,10 @@ reset time: {}",
                 //basic functionality. Send to specified name on specified time
                 Request::Refresh => {
                     (is_sent, status) = check_can_send(
-                        &people_table,
+                        &task_tables,
+                        &short_days,
                         &config,
+                        transport,
                         is_sent,
                         status,
                         resend,
@@ -95,17 +110,25 @@ reset time: {}",
                     continue;
                 }
 
-                //switch names of between two dates
-                Request::Switch(date1, date2) => {
-                    if people_table.contains_key(&date1) && people_table.contains_key(&date2) {
-                        let sol = people_table.get(&date1).unwrap().clone();
-                        people_table.insert(date1, people_table.get(&date2).unwrap().clone());
-                        people_table.insert(date2, sol);
-                        reader::table::update_source_table(&output_path, &people_table)
+                //switch names of between two dates, or between two of their assignees
+                Request::Switch(task, (date1, name1), (date2, name2)) => {
+                    let mut switched = false;
+                    let people_tables = match select_tables(&mut task_tables, &task) {
+                        Ok(people_tables) => people_tables,
+                        Err(e) => {
+                            transmitting.send(vec![Box::new(e)]).unwrap();
+                            continue;
+                        }
+                    };
+                    for people_table in people_tables {
+                        switched |= switch_people(people_table, date1, name1.as_deref(), date2, name2.as_deref());
+                    }
+                    if switched {
+                        reader::table::update_source_table(&output_path, &task_tables, &short_days)
                             .unwrap();
-                        print_around_date(&people_table, 5, &vec![date1, date2])
+                        print_around_date(&task_tables, 5, &vec![date1, date2])
                     } else {
-                        vec![Box::new("Dates provided don't exist in table")]
+                        vec![Box::new("Dates or names provided don't exist in table")]
                     }
                 }
 
@@ -117,15 +140,35 @@ reset time: {}",
 
                 //Drop a name from the table completly, collapse the next names to the current one's date, or postpone by 
                 //moving all names from given date one entry forward
-                Request::Drop(drop_type, date) => {
-                    if people_table.contains_key(&date) {
-                        drop_name(&mut people_table, drop_type, date, config);
-                        reader::table::update_source_table(&output_path, &people_table)
+                Request::Drop(drop_type, task, date, name) => {
+                    let mut dropped = false;
+                    //Without the file there are no excluded dates to skip when postponing
+                    let excluded_dates = reader::table::get_excluded_dates(&paths::get_output_path("excluded_dates.csv"))
+                        .unwrap_or_default();
+                    let people_tables = match select_tables(&mut task_tables, &task) {
+                        Ok(people_tables) => people_tables,
+                        Err(e) => {
+                            transmitting.send(vec![Box::new(e)]).unwrap();
+                            continue;
+                        }
+                    };
+                    for people_table in people_tables {
+                        let assigned = match people_table.get(&date) {
+                            Some(people) => name.is_none() || assignee_index(people, name.as_deref()).is_some(),
+                            None => false,
+                        };
+                        if assigned {
+                            drop_name(people_table, drop_type, date, name.as_deref(), config, &excluded_dates);
+                            dropped = true;
+                        }
+                    }
+                    if dropped {
+                        reader::table::update_source_table(&output_path, &task_tables, &short_days)
                             .unwrap();
-                        print_around_date(&people_table, 5, &vec![date])
+                        print_around_date(&task_tables, 5, &vec![date])
                     }
                     else{
-                        vec![]
+                        vec![Box::new("Date or name provided isn't in the table")]
                     }
                 }
 
@@ -145,12 +188,12 @@ reset time: {}",
                     let weeks = now.iter_weeks().take(num_of_weeks);
                     for week in weeks {
                         week.iter_days().take(7).for_each(|day| {
-                            if people_table.contains_key(&day) {
+                            if let Some(assignees) = describe_date(&task_tables, &day) {
                                 output.push(Box::new(format!(
                                     "{} {} | {}",
                                     day.weekday().to_string(),
                                     day,
-                                    people_table.get(&day).unwrap().name
+                                    assignees
                                 )));
                             }
                         });
@@ -164,12 +207,39 @@ reset time: {}",
     }
 }
 
+//Tables of the given task. Without one the table has to hold a single task.
+fn select_tables<'a>(
+    task_tables: &'a mut TaskTables,
+    task: &Option<String>,
+) -> Result<Vec<&'a mut HashMap<NaiveDate, Vec<Person>>>, String> {
+    if task.is_none() && task_tables.len() > 1 {
+        let tasks: Vec<&str> = task_tables.keys().map(|t| t.as_str()).collect();
+        return Err(format!(
+            "The table has several tasks ({}), give the task name before the date",
+            tasks.join(", ")
+        ));
+    }
+    Ok(task_tables
+        .iter_mut()
+        .filter(|(name, _)| task.as_ref().is_none_or(|task| task == *name))
+        .map(|(_, table)| table)
+        .collect())
+}
+
 fn drop_name(
-    people_table: &mut HashMap<NaiveDate, Person>,
+    people_table: &mut HashMap<NaiveDate, Vec<Person>>,
     drop_type: DropType,
     date: NaiveDate,
+    name: Option<&str>,
     config: &config::Config,
+    excluded_dates: &[HebDate],
 ) {
+    if name.is_some() {
+        if let Some(index) = people_table.get(&date).and_then(|p| assignee_index(p, name)) {
+            drop_assignee(people_table, drop_type, date, index, config, excluded_dates);
+        }
+        return;
+    }
     match drop_type {
         DropType::Clean => _ = people_table.remove(&date),
         DropType::Collapse => {
@@ -201,17 +271,10 @@ fn drop_name(
             //Add "Next eligible date" functionality to table maker.
             //Move modifying functionality to table_maker.
             let mut table = people_table.clone();
-            let latest = table.keys().max().unwrap();
-            if let Ok(excluded_dates) = reader::table::get_excluded_dates() {
+            if let Some(next_date) = next_free_date(&table, config, excluded_dates) {
                 let mut dates: Vec<NaiveDate> =
                     table.keys().filter(|d| **d >= date).cloned().collect();
-                //find next date that isn't a weekend and isn't in the excluded days section
-                let mut next_date = latest.iter_days().filter(|x: &NaiveDate| {
-                    !config.weekend.contains(&x.weekday())
-                        && excluded_dates.iter().filter(|p| p.date == *x).count() == 0
-                });
-                _ = next_date.next();
-                dates.push(next_date.next().unwrap());
+                dates.push(next_date);
                 dates.sort();
                 let mut iter = dates.into_iter().rev();
                 let mut curr_date = iter.next();
@@ -238,17 +301,156 @@ fn drop_name(
     }
 }
 
+//Drop a single assignee of a date. Collapse and postpone only move the people assigned in the
+//same position on the following dates.
+fn drop_assignee(
+    people_table: &mut HashMap<NaiveDate, Vec<Person>>,
+    drop_type: DropType,
+    date: NaiveDate,
+    index: usize,
+    config: &config::Config,
+    excluded_dates: &[HebDate],
+) {
+    let mut lane: Vec<NaiveDate> = people_table
+        .iter()
+        .filter(|(d, people)| **d >= date && people.len() > index)
+        .map(|(d, _)| *d)
+        .collect();
+    lane.sort();
+    match drop_type {
+        DropType::Clean => remove_assignee(people_table, date, index),
+        DropType::Collapse => {
+            for pair in lane.windows(2) {
+                let next = people_table[&pair[1]][index].clone();
+                people_table.get_mut(&pair[0]).unwrap()[index] = next;
+            }
+            remove_assignee(people_table, *lane.last().unwrap(), index);
+        }
+        DropType::Postpone => {
+            if let Some(next_date) = next_free_date(people_table, config, excluded_dates) {
+                let last = people_table[lane.last().unwrap()][index].clone();
+                for pair in lane.windows(2).rev() {
+                    let prev = people_table[&pair[0]][index].clone();
+                    people_table.get_mut(&pair[1]).unwrap()[index] = prev;
+                }
+                people_table.insert(next_date, vec![last]);
+                remove_assignee(people_table, date, index);
+            }
+        }
+    }
+}
+
+fn remove_assignee(people_table: &mut HashMap<NaiveDate, Vec<Person>>, date: NaiveDate, index: usize) {
+    if let Some(people) = people_table.get_mut(&date) {
+        people.remove(index);
+        if people.is_empty() {
+            people_table.remove(&date);
+        }
+    }
+}
+
+//First date after the end of the table that isn't a weekend and isn't in the excluded days section
+fn next_free_date(
+    people_table: &HashMap<NaiveDate, Vec<Person>>,
+    config: &config::Config,
+    excluded_dates: &[HebDate],
+) -> Option<NaiveDate> {
+    //Add "Next eligible date" functionality to table maker.
+    let latest = people_table.keys().max()?;
+    let calendar = WorkCalendar::from_config(config);
+    let mut next_date = calendar.workdays_from(latest).filter(|x: &NaiveDate| {
+        excluded_dates.iter().filter(|p| p.date == *x).count() == 0
+    });
+    _ = next_date.next();
+    next_date.next()
+}
+
+//Position of the named person among a date's assignees. Without a name the date must have a single assignee.
+fn assignee_index(people: &[Person], name: Option<&str>) -> Option<usize> {
+    match name {
+        Some(name) => people.iter().position(|p| p.name == name),
+        None if people.len() == 1 => Some(0),
+        None => None,
+    }
+}
+
+//Swap the people of two dates. When a name is given only that assignee is swapped.
+fn switch_people(
+    people_table: &mut HashMap<NaiveDate, Vec<Person>>,
+    date1: NaiveDate,
+    name1: Option<&str>,
+    date2: NaiveDate,
+    name2: Option<&str>,
+) -> bool {
+    let (people1, people2) = match (people_table.get(&date1), people_table.get(&date2)) {
+        (Some(people1), Some(people2)) => (people1.clone(), people2.clone()),
+        _ => return false,
+    };
+    if name1.is_none() && name2.is_none() {
+        people_table.insert(date1, people2);
+        people_table.insert(date2, people1);
+        return true;
+    }
+    match (assignee_index(&people1, name1), assignee_index(&people2, name2)) {
+        (Some(index1), Some(index2)) => {
+            people_table.get_mut(&date1).unwrap()[index1] = people2[index2].clone();
+            people_table.get_mut(&date2).unwrap()[index2] = people1[index1].clone();
+            true
+        }
+        _ => false,
+    }
+}
+
+fn names(people: &[Person]) -> String {
+    people
+        .iter()
+        .map(|p| p.name.as_str())
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
+
+//Assignees of every task on a date. Task names are only shown when there are several tasks.
+fn describe_date(task_tables: &TaskTables, date: &NaiveDate) -> Option<String> {
+    let tasks: Vec<String> = task_tables
+        .iter()
+        .filter_map(|(task, table)| {
+            let people = table.get(date)?;
+            if task_tables.len() == 1 {
+                Some(names(people))
+            } else {
+                Some(format!("{}: {}", task, names(people)))
+            }
+        })
+        .collect();
+    if tasks.is_empty() {
+        None
+    } else {
+        Some(tasks.join(" | "))
+    }
+}
+
+fn describe_day(task_tables: &TaskTables, add_days: i64) -> Option<String> {
+    describe_date(
+        task_tables,
+        &chrono::Local::now()
+            .date_naive()
+            .checked_add_signed(chrono::Duration::days(add_days))
+            .unwrap(),
+    )
+}
+
 //Check if it is possible to send an SMS message and return status.
 fn check_can_send(
-    people_table: &HashMap<NaiveDate, Person>,
+    task_tables: &TaskTables,
+    short_days: &HashSet<NaiveDate>,
     config:&Config,
+    transport: &dyn MessageTransport,
     is_sent: bool,
     status: String,
     resend: bool,
 ) -> (bool, String) {
     let send_time = &config.send_time;
     let reset_time = &config.reset_time;
-    let maintainer = &config.maintainer;
     let alert_day = &config.alert_day;
     let mut status = status;
     let mut is_sent = is_sent;
@@ -257,49 +459,84 @@ fn check_can_send(
         status.clear();
     }
     if !is_sent && is_close_to_time(send_time) || resend {
-        (is_sent, status) = send_from_table(&people_table,&config);
+        (is_sent, status) = send_from_table(task_tables,short_days,config,transport);
 
-        //send to maintainer
+        //send to maintainer, a failed alert is only reported in the status
         if !is_sent && chrono::Local::now().date().weekday() == *alert_day {
-            send_to(maintainer, "Maintainer alert",&config).unwrap();
+            if let Err(e) = alert_maintainer(config, transport) {
+                status = format!("{}\nMaintainer alert failed: {}", status, e);
+            }
             is_sent = true;
         }
     }
     (is_sent, status)
 }
 
-//send sms message to number found in table
-fn send_from_table(people_table: &HashMap<NaiveDate, Person>,config: &Config) -> (bool, String) {
-    match get_name_from_table(&people_table, 0) {
-        Some(person) => {
-            if let Ok(res) = send_to(&person.phone, &format!("{}: {}", person.name, MESSAGE),config) {
-                let num: u32 = res
-                    .split_whitespace()
-                    .filter(|s| s.parse::<u32>().is_ok())
-                    .next()
-                    .get_or_insert("0")
-                    .parse()
-                    .unwrap();
-                if num > 0 {
-                    (true, res)
-                } else {
-                    (false, res)
-                }
-            } else {
-                // status = "Failed".to_string();
-                // is_sent = false;
-                (false, "Failed".to_string())
+fn alert_maintainer(config: &Config, transport: &dyn MessageTransport) -> Result<String, Box<dyn std::error::Error>> {
+    let maintainer = Person {
+        name: "maintainer".to_string(),
+        phone: config.maintainer.clone(),
+        chat_id: config.maintainer_chat_id.clone(),
+        ..Default::default()
+    };
+    transport.send(&maintainer, "Maintainer alert")
+}
+
+//send every task's sms message to every number found in its table for today
+fn send_from_table(task_tables: &TaskTables,short_days: &HashSet<NaiveDate>,config: &Config,transport: &dyn MessageTransport) -> (bool, String) {
+    let short_day = short_days.contains(&chrono::Local::now().date_naive());
+    let mut results: Vec<(bool, String)> = vec![];
+    for (task, people_table) in task_tables {
+        let message = task_message(config, task);
+        if let Some(people) = get_name_from_table(people_table, 0) {
+            for person in &people {
+                results.push(send_to_person(person, task, &message, short_day, transport));
             }
         }
-        None => (false, "No person found".to_string()),
+    }
+    if results.is_empty() {
+        return (false, "No person found".to_string());
+    }
+    (
+        results.iter().all(|(sent, _)| *sent),
+        results
+            .into_iter()
+            .map(|(_, status)| status)
+            .collect::<Vec<String>>()
+            .join("\n"),
+    )
+}
+
+//The task's own message, or the cleaning reminder naming the task it's for
+fn task_message(config: &Config, task: &str) -> String {
+    match config.tasks.iter().find(|t| t.name == task).and_then(|t| t.message.clone()) {
+        Some(message) => message,
+        None if task != DEFAULT_TASK => format!("{} - {}\n{}", MESSAGE, task, MESSAGE_NOTE),
+        None => format!("{}\n{}", MESSAGE, MESSAGE_NOTE),
+    }
+}
+
+fn send_to_person(person: &Person, task: &str, message: &str, short_day: bool, transport: &dyn MessageTransport) -> (bool, String) {
+    let mut message = format!("{}: {}", person.name, message);
+    if short_day {
+        message = format!("{}\n{}", message, SHORT_DAY_MESSAGE);
+    }
+    let reminder = Reminder {
+        task,
+        date: chrono::Local::now().date_naive(),
+        message: &message,
+    };
+    match transport.send_reminder(person, &reminder) {
+        Ok(status) => (true, status),
+        Err(e) => (false, e.to_string()),
     }
 }
 
 //get name-number pair from the table
 fn get_name_from_table(
-    people_table: &HashMap<NaiveDate, Person>,
+    people_table: &HashMap<NaiveDate, Vec<Person>>,
     add_days: i64,
-) -> Option<Person> {
+) -> Option<Vec<Person>> {
     people_table
         .get(
             &chrono::Local::now()
@@ -319,12 +556,13 @@ fn is_close_to_time(time: &NaiveTime) -> bool {
 }
 
 //print range of entries around given date
-fn print_around_date(table: &HashMap<NaiveDate, Person>, range: usize, dates: &Vec<NaiveDate>) ->Vec<Box<dyn Display+Send>>{
-    if dates.is_empty() || table.is_empty() {
+fn print_around_date(task_tables: &TaskTables, range: usize, dates: &Vec<NaiveDate>) ->Vec<Box<dyn Display+Send>>{
+    let mut table_dates: Vec<NaiveDate> = task_tables.values().flat_map(|t| t.keys().cloned()).collect();
+    if dates.is_empty() || table_dates.is_empty() {
         return vec![];
     }
-    let mut table_dates: Vec<NaiveDate> = table.keys().map(|x| x.clone()).collect();
     table_dates.sort();
+    table_dates.dedup();
     let mut dates_to_print = vec![];
     for date in dates {
         let index;
@@ -363,11 +601,11 @@ fn print_around_date(table: &HashMap<NaiveDate, Person>, range: usize, dates: &V
     let mut output = Vec::<Box<dyn std::fmt::Display+Send>>::new();
     for date in dates_to_print {
         if dates.contains(date) {
-            output.push(Box::new(format!("{} | {}", date, table.get(date).unwrap().name)
+            output.push(Box::new(format!("{} | {}", date, describe_date(task_tables, date).unwrap())
                     .red()
                     .bold()));
         } else {
-            output.push(Box::new(format!("{} | {}", date, table.get(date).unwrap().name)));
+            output.push(Box::new(format!("{} | {}", date, describe_date(task_tables, date).unwrap())));
         }
     }
     output
@@ -386,13 +624,13 @@ where
 pub enum Request {
     Status,
     Refresh,
-    Switch(NaiveDate, NaiveDate),
+    Switch(Option<String>, (NaiveDate, Option<String>), (NaiveDate, Option<String>)),
     Resend,
-    Drop(DropType, NaiveDate),
+    Drop(DropType, Option<String>, NaiveDate, Option<String>),
     Show(usize),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum DropType {
     Clean,
     Collapse,
@@ -403,6 +641,7 @@ pub enum DropType {
 mod tests {
 
     use super::*;
+    use table_configs::config::DEFAULT_TASK;
 
     #[test]
     fn drop_clean() {
@@ -417,25 +656,39 @@ mod tests {
             .unwrap()
             .clone();
         let following_name = name_table.get(&following_date).unwrap().clone();
-        drop_name(&mut name_table, DropType::Clean, drop_date, &config);
+        drop_name(&mut name_table, DropType::Clean, drop_date, None, &config, &[]);
         assert!(name_table.get(&drop_date).is_none());
         assert_eq!(
-            name_table.get(&following_date).unwrap().name,
-            following_name.name
+            name_table.get(&following_date).unwrap()[0].name,
+            following_name[0].name
         );
     }
     #[test]
     fn drop_post() {
-        let mut data = inititate(DropType::Collapse);
+        let data = inititate(DropType::Postpone);
+        //Excluded dates come from the file next to the table
+        let excluded_path = "./test_excluded_dates.csv";
+        std::fs::write(excluded_path, "title,date\nHoliday,2022-05-22\n").unwrap();
+        let excluded_dates = reader::table::get_excluded_dates(excluded_path);
+        std::fs::remove_file(excluded_path).unwrap();
+        let excluded_dates = excluded_dates.unwrap();
+
+        let mut name_table = data.name_table.clone();
+        drop_name(&mut name_table, DropType::Postpone, data.drop_date, None, &data.config, &[]);
+        println!("{:?}", &name_table);
+        assert!(name_table.contains_key(&NaiveDate::from_ymd_opt(2022, 5, 22).unwrap()));
+
         let mut name_table = data.name_table;
         drop_name(
             &mut name_table,
             DropType::Postpone,
             data.drop_date,
+            None,
             &data.config,
+            &excluded_dates,
         );
-        println!("{:?}", &name_table);
-        assert!(name_table.contains_key(&NaiveDate::from_ymd(2022, 5, 22)));
+        assert!(!name_table.contains_key(&NaiveDate::from_ymd_opt(2022, 5, 22).unwrap()));
+        assert!(name_table.contains_key(&NaiveDate::from_ymd_opt(2022, 5, 23).unwrap()));
     }
 
     #[test]
@@ -456,9 +709,9 @@ mod tests {
 
         let last_date = name_table.keys().max().unwrap().clone();
 
-        drop_name(&mut name_table, DropType::Collapse, drop_date, &config);
+        drop_name(&mut name_table, DropType::Collapse, drop_date, None, &config, &[]);
 
-        assert!(name_table.get(&drop_date).unwrap().name == following_name.name);
+        assert!(name_table.get(&drop_date).unwrap()[0].name == following_name[0].name);
         assert!(!name_table.keys().any(|x| *x == last_date));
         assert!(
             name_table
@@ -471,56 +724,207 @@ mod tests {
             .iter()
             .filter(|x| *x.0 >= drop_date)
             .map(|(k, v)| (k.clone(), v.clone()))
-            .collect::<Vec<(NaiveDate, Person)>>();
+            .collect::<Vec<(NaiveDate, Vec<Person>)>>();
         res.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap());
 
         let mut org = org_table
             .iter()
             .filter(|x| *x.0 >= drop_date)
             .map(|(k, v)| (k.clone(), v.clone()))
-            .collect::<Vec<(NaiveDate, Person)>>();
+            .collect::<Vec<(NaiveDate, Vec<Person>)>>();
         org.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap());
         let mut org_iter = org.iter();
         _ = org_iter.next();
         let zipped = org_iter.zip(res.iter());
         for (org, res) in zipped {
-            assert_eq!(org.1.name, res.1.name);
+            assert_eq!(org.1[0].name, res.1[0].name);
         }
     }
 
-    fn inititate(drop_type: DropType) -> Data {
+    #[test]
+    fn drop_and_switch_assignee() {
+        let data = inititate(DropType::Clean);
         let table = "name,number,date
-John,9725130465,2022-05-18
-Maddy,972541235467,2022-05-17
-Kaladin,972468578448, 2022-05-16";
-        let table_path = format!("./test_table_{:?}.csv", drop_type);
-        std::fs::write(&table_path, table).unwrap();
+Anna,1,2022-05-16
+Ben,2,2022-05-16
+Carl,3,2022-05-17
+Dana,4,2022-05-17
+Eli,5,2022-05-18
+Fay,6,2022-05-18";
+        let table_path = "./test_table_assignees.csv";
+        std::fs::write(table_path, table).unwrap();
+        let mut name_table = reader::table::get_people_table(table_path)
+            .unwrap()
+            .remove(DEFAULT_TASK)
+            .unwrap();
+        std::fs::remove_file(table_path).unwrap();
+        let day = |d| NaiveDate::from_ymd_opt(2022, 5, d).unwrap();
+
+        assert!(switch_people(&mut name_table, day(16), Some("Ben"), day(18), Some("Eli")));
+        assert_eq!(names(&name_table[&day(16)]), "Anna, Eli");
+        assert_eq!(names(&name_table[&day(18)]), "Ben, Fay");
+        assert!(!switch_people(&mut name_table, day(16), Some("Carl"), day(17), None));
+
+        drop_name(&mut name_table, DropType::Collapse, day(16), Some("Anna"), &data.config, &[]);
+        assert_eq!(names(&name_table[&day(16)]), "Carl, Eli");
+        assert_eq!(names(&name_table[&day(17)]), "Ben, Dana");
+        assert_eq!(names(&name_table[&day(18)]), "Fay");
+
+        drop_name(&mut name_table, DropType::Clean, day(17), Some("Dana"), &data.config, &[]);
+        assert_eq!(names(&name_table[&day(17)]), "Ben");
+    }
+
+    fn test_config() -> Config {
         let config = r#"{
     "start_date": "2022-05-18",
     "range": 180,
-    "output_path":"./output/",
+    "output_file_name":"test",
     "send_time":"09:00:00",
     "reset_time":"01:00:00",
     "maintainer":"",
-    "alert_day":"5",
-    "weekend":[5,6,7]
+    "alert_day":5,
+    "weekend":[5,6,7],
+    "key":"",
+    "user":"",
+    "pass":"",
+    "sender":""
     }"#;
-        let config_path = format!("./test_config_{:?}.csv", drop_type);
+        Config::from(serde_json::from_str(config).unwrap())
+    }
 
-        std::fs::write(&config_path, config).unwrap();
-        let table = reader::table::get_people_table(&table_path).unwrap();
-        let config = reader::config::load_config(&config_path).unwrap();
+    fn inititate(drop_type: DropType) -> Data {
+        let table = "name,number,date
+John,9725130465,2022-05-18
+Maddy,972541235467,2022-05-17
+Kaladin,972468578448, 2022-05-16";
+        let table_path = format!("./test_table_{:?}.csv", drop_type);
+        std::fs::write(&table_path, table).unwrap();
+        let table = reader::table::get_people_table(&table_path)
+            .unwrap()
+            .remove(DEFAULT_TASK)
+            .unwrap();
+        let config = test_config();
         std::fs::remove_file(&table_path).unwrap();
-        std::fs::remove_file(&config_path).unwrap();
         Data {
-            drop_date: NaiveDate::from_ymd(2022, 5, 18),
+            drop_date: NaiveDate::from_ymd_opt(2022, 5, 17).unwrap(),
             name_table: table,
             config,
         }
     }
     struct Data {
         drop_date: NaiveDate,
-        name_table: HashMap<NaiveDate, Person>,
-        config: ConfigReader,
+        name_table: HashMap<NaiveDate, Vec<Person>>,
+        config: Config,
+    }
+
+    #[test]
+    fn task_tables() {
+        let table = "name,number,date,short_day,task
+Anna,1,2022-05-16,false,kitchen
+Ben,2,2022-05-16,false,trash
+Carl,3,2022-05-17,false,kitchen";
+        let table_path = "./test_table_tasks.csv";
+        std::fs::write(table_path, table).unwrap();
+        let mut task_tables = reader::table::get_people_table(table_path).unwrap();
+        std::fs::remove_file(table_path).unwrap();
+        let day = |d| NaiveDate::from_ymd_opt(2022, 5, d).unwrap();
+
+        assert_eq!(
+            describe_date(&task_tables, &day(16)).unwrap(),
+            "kitchen: Anna | trash: Ben"
+        );
+        assert_eq!(describe_date(&task_tables, &day(17)).unwrap(), "kitchen: Carl");
+        assert_eq!(select_tables(&mut task_tables, &Some("trash".to_string())).unwrap().len(), 1);
+        assert!(select_tables(&mut task_tables, &None).is_err());
+        task_tables.remove("trash");
+        assert_eq!(select_tables(&mut task_tables, &None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn default_task_message() {
+        let mut config = test_config();
+        config.tasks = vec![
+            config::Task {
+                name: "kitchen".to_string(),
+                message: Some("clean".to_string()),
+                ..Default::default()
+            },
+            config::Task {
+                name: "trash".to_string(),
+                ..Default::default()
+            },
+        ];
+        assert_eq!(task_message(&config, "kitchen"), "clean");
+        assert_eq!(task_message(&config, "trash"), format!("{} - trash\n{}", MESSAGE, MESSAGE_NOTE));
+        assert_eq!(task_message(&config, DEFAULT_TASK), format!("{}\n{}", MESSAGE, MESSAGE_NOTE));
+    }
+
+    //Records the messages instead of sending them
+    struct MockTransport {
+        status: Result<String, String>,
+        sent: std::sync::Mutex<Vec<(String, String)>>,
+    }
+    impl MessageTransport for MockTransport {
+        fn send(&self, person: &Person, message: &str) -> Result<String, Box<dyn std::error::Error>> {
+            self.sent.lock().unwrap().push((person.phone.clone(), message.to_string()));
+            self.status.clone().map_err(|e| e.into())
+        }
+    }
+
+    #[test]
+    fn send_through_transport() {
+        let person = Person {
+            name: "Anna".to_string(),
+            phone: "1".to_string(),
+            ..Default::default()
+        };
+        let transport = |status: Result<&str, &str>| MockTransport {
+            status: status.map(|s| s.to_string()).map_err(|e| e.to_string()),
+            sent: std::sync::Mutex::new(vec![]),
+        };
+
+        let ok = transport(Ok("200 OK"));
+        assert_eq!(send_to_person(&person, "kitchen", "clean", true, &ok), (true, "200 OK".to_string()));
+        assert_eq!(
+            ok.sent.lock().unwrap()[0],
+            ("1".to_string(), format!("Anna: clean\n{}", SHORT_DAY_MESSAGE))
+        );
+        //Any reply the transport accepts counts as sent
+        assert!(send_to_person(&person, "kitchen", "clean", false, &transport(Ok("Dry run, logged to log"))).0);
+        assert_eq!(
+            send_to_person(&person, "kitchen", "clean", false, &transport(Err("offline"))),
+            (false, "offline".to_string())
+        );
+        let offline = sender::Sms4free {
+            base_url: fake_server::unreachable(),
+            key: String::new(),
+            user: String::new(),
+            pass: String::new(),
+            sender: String::new(),
+        };
+        assert!(!send_to_person(&person, "kitchen", "clean", false, &offline).0);
+    }
+
+    #[test]
+    fn maintainer_alert_failures() {
+        use fake_server::{body, serve};
+        let mut config = test_config();
+        config.maintainer_chat_id = String::new();
+        let (base_url, handle) = serve(vec![("500 Internal Server Error", "{}"), ("200 OK", "{}")]);
+        let telegram = sender::Telegram {
+            base_url,
+            token: "123:abc".to_string(),
+        };
+        //Without a chat ID nothing is sent
+        assert!(alert_maintainer(&config, &telegram).is_err());
+
+        config.maintainer_chat_id = "7".to_string();
+        let error = alert_maintainer(&config, &telegram).unwrap_err();
+        assert_eq!(error.to_string(), "Telegram replied 500 Internal Server Error");
+        assert_eq!(alert_maintainer(&config, &telegram).unwrap(), "200 OK");
+
+        let requests = handle.join().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(body(&requests[1]), serde_json::json!({"chat_id": "7", "text": "Maintainer alert"}));
     }
 }
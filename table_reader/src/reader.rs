@@ -1,45 +1,98 @@
 pub mod table {
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap, HashSet};
 
     use chrono::NaiveDate;
     use csv::{self, Reader, Writer};
-    use table_configs::paths;
-    use table_maker::{HebDateRaw, NamesTableRaw, Person};
+    use table_configs::{
+        config::{Config, DEFAULT_TASK},
+        paths,
+    };
+    use table_maker::{parse_candidates_from_file, HebDateRaw, NamesTableRaw, Person};
 
-    pub fn get_people_table(
-        filepath: &str,
-    ) -> Result<HashMap<NaiveDate, Person>, Box<dyn std::error::Error>> {
+    ///Every task's table, by task name and date.
+    pub type TaskTables = BTreeMap<String, HashMap<NaiveDate, Vec<Person>>>;
+
+    pub fn get_people_table(filepath: &str) -> Result<TaskTables, Box<dyn std::error::Error>> {
         let file = std::fs::read_to_string(&filepath).expect("NO TABLE WAS FOUND OR CREATED.");
-        let mut map = HashMap::<NaiveDate, Person>::new();
+        let mut map = TaskTables::new();
 
         let mut rdr = Reader::from_reader(file.as_bytes());
         let iter = rdr
             .deserialize()
             .map(|x: Result<NamesTableRaw, csv::Error>| x.unwrap());
 
+        //Every assignee of a date has a row of their own. Rows without a task belong to the default one.
         for row in iter {
-            map.insert(
-                NaiveDate::parse_from_str(&row.date, "%Y-%m-%d").unwrap(),
-                Person {
+            let task = if row.task.is_empty() { DEFAULT_TASK.to_string() } else { row.task };
+            map.entry(task)
+                .or_default()
+                .entry(NaiveDate::parse_from_str(row.date.trim(), "%Y-%m-%d").unwrap())
+                .or_default()
+                .push(Person {
                     name: row.name,
                     phone: row.number,
-                },
-            );
+                    ..Default::default()
+                });
         }
         Ok(map)
     }
+    //Dates marked as short days when the table was created
+    pub fn get_short_days(filepath: &str) -> Result<HashSet<NaiveDate>, Box<dyn std::error::Error>> {
+        let file = std::fs::read_to_string(filepath)?;
+        let mut rdr = Reader::from_reader(file.as_bytes());
+        let mut short_days = HashSet::new();
+        for row in rdr.deserialize() {
+            let row: NamesTableRaw = row?;
+            if row.short_day {
+                short_days.insert(NaiveDate::parse_from_str(row.date.trim(), "%Y-%m-%d")?);
+            }
+        }
+        Ok(short_days)
+    }
+    //The table only keeps names and phones, email addresses, channels and chat IDs come from the
+    //rosters.
+    //A roster that can't be read leaves its people on SMS.
+    pub fn add_contacts(
+        task_tables: &mut TaskTables,
+        config: &Config,
+    ) {
+        for task in &config.tasks {
+            let roster = match parse_candidates_from_file(&paths::get_roster_path(&task.roster)) {
+                Ok(roster) => roster,
+                Err(_) => continue,
+            };
+            let table = match task_tables.get_mut(&task.name) {
+                Some(table) => table,
+                None => continue,
+            };
+            for person in table.values_mut().flatten() {
+                if let Some(contact) = roster.iter().find(|p| p.name == person.name) {
+                    person.email = contact.email.clone();
+                    person.channel = contact.channel;
+                    person.chat_id = contact.chat_id.clone();
+                }
+            }
+        }
+    }
     pub fn update_source_table(
         filepath: &str,
-        table: &HashMap<NaiveDate, Person>,
+        task_tables: &TaskTables,
+        short_days: &HashSet<NaiveDate>,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let mut wtr = Writer::from_writer(vec![]);
         let mut rows = Vec::<NamesTableRaw>::new();
-        for p in table {
-            rows.push(NamesTableRaw {
-                date: String::from(p.0.format("%Y-%m-%d").to_string()),
-                name: String::from(&p.1.name),
-                number: String::from(&p.1.phone),
-            });
+        for (task, table) in task_tables {
+            for (date, people) in table {
+                for person in people {
+                    rows.push(NamesTableRaw {
+                        date: date.format("%Y-%m-%d").to_string(),
+                        name: String::from(&person.name),
+                        number: String::from(&person.phone),
+                        short_day: short_days.contains(date),
+                        task: task.clone(),
+                    });
+                }
+            }
         }
         rows.sort_by(|a, b| {
             NaiveDate::parse_from_str(&a.date, "%Y-%m-%d")
@@ -53,8 +106,8 @@ pub mod table {
         std::fs::write(filepath, data)?;
         Ok(())
     }
-    pub fn get_excluded_dates() -> Result<Vec<table_maker::HebDate>, Box<dyn std::error::Error>> {
-        let file = std::fs::read_to_string(&paths::get_output_path("excluded_dates.csv"))?;
+    pub fn get_excluded_dates(filepath: &str) -> Result<Vec<table_maker::HebDate>, Box<dyn std::error::Error>> {
+        let file = std::fs::read_to_string(filepath)?;
         let mut rdr = Reader::from_reader(file.as_bytes());
         let iter = rdr
             .deserialize()
@@ -0,0 +1,75 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use table_maker::Person;
+
+use super::MessageTransport;
+
+///Logs the messages to the console and to a file instead of sending them.
+pub struct DryRun {
+    pub log: String,
+}
+
+impl MessageTransport for DryRun {
+    fn send(&self, person: &Person, message: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let line = format!(
+            "{} to {} ({}): {:?}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            person.name,
+            recipient(person),
+            message
+        );
+        println!("{}", line);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.log)?;
+        writeln!(file, "{}", line)?;
+        //Counts as sent, like the status of a successful delivery
+        Ok(format!("Dry run, logged to {}", self.log))
+    }
+}
+
+//Every address the person could be reached at
+fn recipient(person: &Person) -> String {
+    [
+        ("phone", &person.phone),
+        ("email", &person.email),
+        ("chat", &person.chat_id),
+    ]
+    .iter()
+    .filter(|(_, address)| !address.is_empty())
+    .map(|(kind, address)| format!("{} {}", kind, address))
+    .collect::<Vec<String>>()
+    .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_messages() {
+        let log = std::env::temp_dir().join(format!("dry_run_{}.log", std::process::id()));
+        let dry_run = DryRun {
+            log: log.to_str().unwrap().to_string(),
+        };
+        let person = Person {
+            name: "Ann".to_string(),
+            phone: "059".to_string(),
+            email: "ann@example.com".to_string(),
+            ..Default::default()
+        };
+        assert!(dry_run.send(&person, "Ann: clean\nshort day").is_ok());
+        assert!(dry_run.send(&person, "Ann: clean").is_ok());
+        let logged = std::fs::read_to_string(&log).unwrap();
+        std::fs::remove_file(&log).unwrap();
+        let lines: Vec<&str> = logged.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0]
+            .ends_with(" to Ann (phone 059, email ann@example.com): \"Ann: clean\\nshort day\""));
+        assert!(
+            chrono::NaiveDateTime::parse_from_str(&lines[0][..19], "%Y-%m-%d %H:%M:%S").is_ok()
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use reqwest::Url;
+use serde::Serialize;
+use table_maker::Person;
+
+use super::{chat_id, http_status, MessageTransport};
+
+///Messages from an account through the Matrix client-server API.
+pub struct Matrix {
+    pub base_url: String,
+    pub access_token: String,
+}
+
+#[derive(Serialize)]
+struct Body<'a> {
+    msgtype: &'a str,
+    body: &'a str,
+}
+
+//Messages sent by this process, keeping transaction IDs unique within a second
+static SENT: AtomicUsize = AtomicUsize::new(0);
+
+impl MessageTransport for Matrix {
+    fn send(&self, person: &Person, message: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let room = chat_id(person)?;
+        //The homeserver ignores a repeated transaction ID, so every message gets its own
+        let transaction = format!(
+            "cleaning{}-{}",
+            chrono::Utc::now().timestamp(),
+            SENT.fetch_add(1, Ordering::SeqCst)
+        );
+        let mut url = Url::parse(&self.base_url)?;
+        url.path_segments_mut()
+            .map_err(|_| format!("{} can't be a Matrix homeserver", self.base_url))?
+            .pop_if_empty()
+            .extend(&["_matrix", "client", "v3", "rooms", room, "send"])
+            .extend(&["m.room.message", transaction.as_str()]);
+        let body = Body {
+            msgtype: "m.text",
+            body: message,
+        };
+        let response = reqwest::Client::new()
+            .put(url)
+            .bearer_auth(&self.access_token)
+            .json(&body)
+            .send()?;
+        http_status("Matrix", response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fake_server::{body, header, serve};
+
+    #[test]
+    fn send_to_room() {
+        let (base_url, handle) = serve(vec![("200 OK", "{}"), ("401 Unauthorized", "{}")]);
+        let matrix = Matrix {
+            base_url: format!("{}/", base_url),
+            access_token: "token".to_string(),
+        };
+        let person = Person {
+            name: "Ann".to_string(),
+            chat_id: "!room:example.org".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(matrix.send(&person, "Ann: clean").unwrap(), "200 OK");
+        assert_eq!(
+            matrix.send(&person, "Ann: clean").unwrap_err().to_string(),
+            "Matrix replied 401 Unauthorized"
+        );
+        let requests = handle.join().unwrap();
+        let path = |request: &str| request.split(' ').nth(1).unwrap().to_string();
+        assert!(path(&requests[0]).starts_with(
+            "/_matrix/client/v3/rooms/!room:example.org/send/m.room.message/cleaning"
+        ));
+        assert!(requests[0].starts_with("PUT "));
+        //Every message has its own transaction ID
+        assert_ne!(path(&requests[0]), path(&requests[1]));
+        assert_eq!(
+            header(&requests[0], "authorization").as_deref(),
+            Some("Bearer token")
+        );
+        assert_eq!(
+            header(&requests[0], "content-type").as_deref(),
+            Some("application/json")
+        );
+        assert_eq!(
+            body(&requests[0]),
+            serde_json::json!({"msgtype": "m.text", "body": "Ann: clean"})
+        );
+    }
+}
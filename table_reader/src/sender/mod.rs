@@ -0,0 +1,248 @@
+use chrono::NaiveDate;
+use reqwest;
+use serde::Serialize;
+use table_configs::config::{Config, TransportKind};
+use table_maker::{Channel, Person};
+
+mod dry_run;
+mod matrix;
+mod smtp;
+mod telegram;
+mod webhook;
+pub use dry_run::DryRun;
+pub use matrix::Matrix;
+pub use smtp::Smtp;
+pub use telegram::Telegram;
+pub use webhook::Webhook;
+
+///A reminder of a task on a date.
+pub struct Reminder<'a> {
+    pub task: &'a str,
+    pub date: NaiveDate,
+    pub message: &'a str,
+}
+
+///Service delivering a message to a person. Returns the service's status of the delivery.
+pub trait MessageTransport: Send {
+    fn send(&self, person: &Person, message: &str) -> Result<String, Box<dyn std::error::Error>>;
+
+    ///Transports that pass on the task and date of a reminder override this.
+    fn send_reminder(
+        &self,
+        person: &Person,
+        reminder: &Reminder,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        self.send(person, reminder.message)
+    }
+}
+
+///The transport selected by "transport" in the config, with email for the people who chose it.
+pub fn transport_for(config: &Config) -> Box<dyn MessageTransport> {
+    Box::new(Channels {
+        transport: configured_transport(config),
+        email: config.email.clone().map(|settings| Smtp { settings }),
+    })
+}
+
+fn configured_transport(config: &Config) -> Box<dyn MessageTransport> {
+    match &config.transport {
+        TransportKind::Sms4free { base_url } => Box::new(Sms4free {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            key: config.key.clone(),
+            user: config.user.clone(),
+            pass: config.pass.clone(),
+            sender: config.sender.clone(),
+        }),
+        TransportKind::Telegram { token, base_url } => Box::new(Telegram {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.clone(),
+        }),
+        TransportKind::Matrix {
+            base_url,
+            access_token,
+        } => Box::new(Matrix {
+            base_url: base_url.clone(),
+            access_token: access_token.clone(),
+        }),
+        TransportKind::Webhook {
+            url,
+            payload,
+            headers,
+            auth,
+        } => Box::new(Webhook {
+            url: url.clone(),
+            payload: payload.clone(),
+            headers: headers.clone(),
+            auth: auth.clone(),
+        }),
+    }
+}
+
+//Only a successful HTTP status counts as sent
+fn http_status(
+    service: &str,
+    response: reqwest::Response,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("{} replied {}", service, status).into());
+    }
+    Ok(status.to_string())
+}
+
+//Chat bots need somewhere to send to
+fn chat_id(person: &Person) -> Result<&str, Box<dyn std::error::Error>> {
+    if person.chat_id.is_empty() {
+        return Err(format!("{} has no chat ID", person.name).into());
+    }
+    Ok(&person.chat_id)
+}
+
+///Sends through the channel each person chose in their names file. The "sms" channel is the
+///configured transport.
+pub struct Channels {
+    pub transport: Box<dyn MessageTransport>,
+    pub email: Option<Smtp>,
+}
+
+impl Channels {
+    fn email(&self) -> Result<&Smtp, Box<dyn std::error::Error>> {
+        self.email
+            .as_ref()
+            .ok_or_else(|| "No \"email\" server in the config".into())
+    }
+
+    fn route<F>(&self, person: &Person, send: F) -> Result<String, Box<dyn std::error::Error>>
+    where
+        F: Fn(&dyn MessageTransport) -> Result<String, Box<dyn std::error::Error>>,
+    {
+        match person.channel {
+            Channel::Sms => send(self.transport.as_ref()),
+            Channel::Email => send(self.email()?),
+            Channel::Both => {
+                let sms = send(self.transport.as_ref());
+                let email = self.email().and_then(|smtp| send(smtp));
+                match (sms, email) {
+                    (Ok(sms), Ok(email)) => Ok(format!("sms: {} | email: {}", sms, email)),
+                    (sms, email) => {
+                        let show = |r: Result<String, Box<dyn std::error::Error>>| {
+                            r.unwrap_or_else(|e| format!("failed ({})", e))
+                        };
+                        Err(format!("sms: {} | email: {}", show(sms), show(email)).into())
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl MessageTransport for Channels {
+    fn send(&self, person: &Person, message: &str) -> Result<String, Box<dyn std::error::Error>> {
+        self.route(person, |transport| transport.send(person, message))
+    }
+
+    fn send_reminder(
+        &self,
+        person: &Person,
+        reminder: &Reminder,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        self.route(person, |transport| {
+            transport.send_reminder(person, reminder)
+        })
+    }
+}
+
+///SMS through the sms4free.co.il API.
+pub struct Sms4free {
+    pub base_url: String,
+    pub key: String,
+    pub user: String,
+    pub pass: String,
+    pub sender: String,
+}
+
+impl MessageTransport for Sms4free {
+    fn send(&self, person: &Person, message: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let client = reqwest::Client::new();
+
+        let body = Body {
+            key: &self.key,
+            user: &self.user,
+            pass: &self.pass,
+            sender: &self.sender,
+            recipient: &person.phone,
+            msg: message,
+        };
+
+        let res = //reqwest::blocking::Client::new()
+        client
+            .post(&format!("{}/ApiSMS/SendSMS", self.base_url))
+            .json(&body)
+            .send()?;
+        http_status("sms4free", res)
+    }
+}
+
+#[derive(Serialize)]
+struct Body<'a> {
+    key: &'a str,
+    user: &'a str,
+    pass: &'a str,
+    sender: &'a str,
+    recipient: &'a str,
+    msg: &'a str,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fake_server::{body, header, serve, unreachable};
+
+    #[test]
+    fn sms4free_base_url() {
+        let (url, handle) = serve(vec![("200 OK", "{}"), ("500 Internal Server Error", "{}")]);
+        let sms4free = Sms4free {
+            base_url: url,
+            key: "key".to_string(),
+            user: "user".to_string(),
+            pass: "pass".to_string(),
+            sender: "sender".to_string(),
+        };
+        let person = Person {
+            name: "Ann".to_string(),
+            phone: "0501".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(sms4free.send(&person, "clean").unwrap(), "200 OK");
+        assert_eq!(
+            sms4free.send(&person, "clean").unwrap_err().to_string(),
+            "sms4free replied 500 Internal Server Error"
+        );
+
+        let requests = handle.join().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].starts_with("POST /ApiSMS/SendSMS "));
+        assert_eq!(
+            header(&requests[0], "content-type").as_deref(),
+            Some("application/json")
+        );
+        assert_eq!(
+            body(&requests[0]),
+            serde_json::json!({
+                "key": "key",
+                "user": "user",
+                "pass": "pass",
+                "sender": "sender",
+                "recipient": "0501",
+                "msg": "clean",
+            })
+        );
+
+        //Without a server the send fails instead of counting as sent
+        let offline = Sms4free {
+            base_url: unreachable(),
+            ..sms4free
+        };
+        assert!(offline.send(&person, "clean").is_err());
+    }
+}
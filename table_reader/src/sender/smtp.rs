@@ -0,0 +1,246 @@
+use std::time::Duration;
+
+use lettre::message::MultiPart;
+use lettre::transport::smtp::authentication::{Credentials, Mechanism};
+use lettre::transport::smtp::client::{Tls, TlsParameters};
+use lettre::{Message, SmtpTransport, Transport};
+use table_configs::config::{EmailConfig, SmtpSecurity};
+use table_maker::Person;
+
+use super::MessageTransport;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+///Email through an SMTP server, with a plain text and an HTML body.
+pub struct Smtp {
+    pub settings: EmailConfig,
+}
+
+impl MessageTransport for Smtp {
+    fn send(&self, person: &Person, message: &str) -> Result<String, Box<dyn std::error::Error>> {
+        if person.email.is_empty() {
+            return Err(format!("{} has no email address", person.name).into());
+        }
+        let mail = Message::builder()
+            .from(self.settings.from.parse()?)
+            .to(person.email.parse()?)
+            .subject(&self.settings.subject)
+            .multipart(MultiPart::alternative_plain_html(
+                message.to_string(),
+                html(message),
+            ))?;
+        let response = self.transport()?.send(&mail)?;
+        let mut status = vec![response.code().to_string()];
+        status.extend(response.message().map(String::from));
+        Ok(status.join(" "))
+    }
+}
+
+impl Smtp {
+    //Never falls back to plain text when the security is starttls or tls
+    fn transport(&self) -> Result<SmtpTransport, Box<dyn std::error::Error>> {
+        let host = &self.settings.host;
+        let tls = match self.settings.security {
+            SmtpSecurity::Plain => Tls::None,
+            SmtpSecurity::Starttls => Tls::Required(TlsParameters::new(host.clone())?),
+            SmtpSecurity::Tls => Tls::Wrapper(TlsParameters::new(host.clone())?),
+        };
+        let mut transport = SmtpTransport::builder_dangerous(host)
+            .port(self.settings.port)
+            .timeout(Some(TIMEOUT))
+            .tls(tls);
+        if !self.settings.username.is_empty() {
+            transport = transport
+                .credentials(Credentials::new(
+                    self.settings.username.clone(),
+                    self.settings.password.clone(),
+                ))
+                .authentication(vec![Mechanism::Plain, Mechanism::Login]);
+        }
+        Ok(transport.build())
+    }
+}
+
+fn html(message: &str) -> String {
+    let escaped = message
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "<br>\n");
+    format!("<html><body><p>{}</p></body></html>", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    //SMTP server advertising `extensions` that accepts a mail when `password` is used to log in.
+    //Returns its port and every line it got.
+    fn sink(
+        extensions: &'static str,
+        password: &'static str,
+    ) -> (u16, std::thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut lines = BufReader::new(stream).lines();
+            let mut received = vec![];
+            let mut login = 0;
+            let accepted = |line: &str| match line == base64::encode(password) {
+                true => "235 2.7.0 accepted\r\n",
+                false => "535 5.7.8 authentication failed\r\n",
+            };
+            writer.write_all(b"220 sink ready\r\n").unwrap();
+            while let Some(Ok(line)) = lines.next() {
+                received.push(line.clone());
+                //The username of AUTH LOGIN is followed by the password
+                let reply = match (login, line.as_str()) {
+                    (1, _) => {
+                        login = 2;
+                        "334 UGFzc3dvcmQ6\r\n".to_string()
+                    }
+                    (2, pass) => {
+                        login = 0;
+                        accepted(pass).to_string()
+                    }
+                    (_, ehlo) if ehlo.starts_with("EHLO ") => format!("250-sink\r\n{}", extensions),
+                    (_, "AUTH LOGIN") => {
+                        login = 1;
+                        "334 VXNlcm5hbWU6\r\n".to_string()
+                    }
+                    (_, plain) if plain.starts_with("AUTH PLAIN ") => {
+                        let login = base64::decode(&plain["AUTH PLAIN ".len()..]).unwrap();
+                        let login = String::from_utf8(login).unwrap();
+                        let sent = login.rsplit('\0').next().unwrap();
+                        accepted(&base64::encode(sent)).to_string()
+                    }
+                    //A real server would start the TLS handshake now
+                    (_, "STARTTLS") => {
+                        writer.write_all(b"220 go ahead\r\n").unwrap();
+                        writer.write_all(b"not a TLS handshake\r\n").unwrap();
+                        break;
+                    }
+                    (_, "DATA") => {
+                        writer.write_all(b"354 go ahead\r\n").unwrap();
+                        for line in lines.by_ref() {
+                            let line = line.unwrap();
+                            if line == "." {
+                                break;
+                            }
+                            received.push(line);
+                        }
+                        "250 2.0.0 queued\r\n".to_string()
+                    }
+                    (_, "QUIT") => {
+                        writer.write_all(b"221 bye\r\n").unwrap();
+                        break;
+                    }
+                    _ => "250 OK\r\n".to_string(),
+                };
+                writer.write_all(reply.as_bytes()).unwrap();
+            }
+            received
+        });
+        (port, handle)
+    }
+
+    fn smtp(port: u16, security: SmtpSecurity, password: &str) -> Smtp {
+        Smtp {
+            settings: EmailConfig {
+                host: "127.0.0.1".to_string(),
+                port,
+                security,
+                username: match password.is_empty() {
+                    true => String::new(),
+                    false => "ann".to_string(),
+                },
+                password: password.to_string(),
+                from: "cleaning@example.com".to_string(),
+                subject: "Cleaning reminder".to_string(),
+            },
+        }
+    }
+
+    fn ann() -> Person {
+        Person {
+            name: "Ann".to_string(),
+            email: "ann@example.com".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn send_to_sink() {
+        let (port, handle) = sink("250 8BITMIME\r\n", "");
+        let smtp = smtp(port, SmtpSecurity::Plain, "");
+        let person = Person {
+            email: String::new(),
+            ..ann()
+        };
+        assert!(smtp.send(&person, "clean").is_err());
+
+        let status = smtp.send(&ann(), "Ann: clean <today>").unwrap();
+        assert_eq!(status, "250 2.0.0 queued");
+        let data = handle.join().unwrap().join("\r\n");
+        assert!(data.contains("To: ann@example.com"));
+        assert!(data.contains("Content-Type: text/plain; charset=utf-8"));
+        assert!(data.contains("Content-Type: text/html; charset=utf-8"));
+        assert!(data.contains("Ann: clean <today>"));
+        assert!(data.contains("<html><body><p>Ann: clean &lt;today&gt;</p></body></html>"));
+    }
+
+    #[test]
+    fn auth_plain_and_login() {
+        let (port, handle) = sink("250 AUTH PLAIN LOGIN\r\n", "secret");
+        smtp(port, SmtpSecurity::Plain, "secret")
+            .send(&ann(), "clean")
+            .unwrap();
+        let received = handle.join().unwrap();
+        assert!(received.iter().any(|line| line.starts_with("AUTH PLAIN ")));
+
+        //Servers offering only LOGIN get the username and password one by one
+        let (port, handle) = sink("250 AUTH LOGIN\r\n", "secret");
+        smtp(port, SmtpSecurity::Plain, "secret")
+            .send(&ann(), "clean")
+            .unwrap();
+        let received = handle.join().unwrap();
+        let login = received.iter().position(|l| l == "AUTH LOGIN").unwrap();
+        assert_eq!(received[login + 1], base64::encode("ann"));
+        assert_eq!(received[login + 2], base64::encode("secret"));
+    }
+
+    #[test]
+    fn auth_failure() {
+        let (port, handle) = sink("250 AUTH PLAIN LOGIN\r\n", "secret");
+        assert!(smtp(port, SmtpSecurity::Plain, "wrong")
+            .send(&ann(), "clean")
+            .is_err());
+        let received = handle.join().unwrap();
+        assert!(!received.iter().any(|line| line.starts_with("MAIL FROM")));
+    }
+
+    #[test]
+    fn starttls_is_required() {
+        //A failed handshake doesn't fall back to sending the password in plain text
+        let (port, handle) = sink("250-STARTTLS\r\n250 AUTH PLAIN LOGIN\r\n", "secret");
+        assert!(smtp(port, SmtpSecurity::Starttls, "secret")
+            .send(&ann(), "clean")
+            .is_err());
+        let received = handle.join().unwrap();
+        assert_eq!(received.last().unwrap(), "STARTTLS");
+        assert!(!received.iter().any(|line| line.starts_with("AUTH")));
+
+        //Neither does a server that doesn't offer STARTTLS
+        let (port, handle) = sink("250 AUTH PLAIN LOGIN\r\n", "secret");
+        assert!(smtp(port, SmtpSecurity::Starttls, "secret")
+            .send(&ann(), "clean")
+            .is_err());
+        let received = handle.join().unwrap();
+        assert!(!received.iter().any(|line| line.starts_with("AUTH")));
+    }
+}
@@ -0,0 +1,78 @@
+use serde::Serialize;
+use table_maker::Person;
+
+use super::{chat_id, http_status, MessageTransport};
+
+///Messages from a bot through Telegram's Bot API.
+pub struct Telegram {
+    pub base_url: String,
+    pub token: String,
+}
+
+#[derive(Serialize)]
+struct Body<'a> {
+    chat_id: &'a str,
+    text: &'a str,
+}
+
+impl MessageTransport for Telegram {
+    fn send(&self, person: &Person, message: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let body = Body {
+            chat_id: chat_id(person)?,
+            text: message,
+        };
+        let response = reqwest::Client::new()
+            .post(&format!("{}/bot{}/sendMessage", self.base_url, self.token))
+            .json(&body)
+            .send()?;
+        http_status("Telegram", response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fake_server::{body, header, serve};
+
+    #[test]
+    fn send_message() {
+        let (base_url, handle) = serve(vec![("200 OK", "{}"), ("403 Forbidden", "{}")]);
+        let telegram = Telegram {
+            base_url,
+            token: "123:abc".to_string(),
+        };
+        let mut person = Person {
+            name: "Ann".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            telegram
+                .send(&person, "Ann: clean")
+                .unwrap_err()
+                .to_string(),
+            "Ann has no chat ID"
+        );
+
+        person.chat_id = "42".to_string();
+        assert_eq!(telegram.send(&person, "Ann: clean").unwrap(), "200 OK");
+        assert_eq!(
+            telegram
+                .send(&person, "Ann: clean")
+                .unwrap_err()
+                .to_string(),
+            "Telegram replied 403 Forbidden"
+        );
+        //Nothing is sent without a chat ID
+        let requests = handle.join().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].starts_with("POST /bot123:abc/sendMessage "));
+        assert_eq!(
+            header(&requests[0], "content-type").as_deref(),
+            Some("application/json")
+        );
+        assert_eq!(
+            body(&requests[0]),
+            serde_json::json!({"chat_id": "42", "text": "Ann: clean"})
+        );
+    }
+}
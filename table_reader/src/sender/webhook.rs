@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+use table_configs::config::WebhookAuth;
+use table_maker::Person;
+
+use super::{http_status, MessageTransport, Reminder};
+
+///POST of a JSON payload to any URL, e.g. a chat server's incoming webhook.
+pub struct Webhook {
+    pub url: String,
+    pub payload: Value,
+    pub headers: BTreeMap<String, String>,
+    pub auth: Option<WebhookAuth>,
+}
+
+impl MessageTransport for Webhook {
+    fn send(&self, person: &Person, message: &str) -> Result<String, Box<dyn std::error::Error>> {
+        //Messages outside the table, like the maintainer alert, have no task
+        self.send_reminder(
+            person,
+            &Reminder {
+                task: "",
+                date: chrono::Local::now().date_naive(),
+                message,
+            },
+        )
+    }
+
+    fn send_reminder(
+        &self,
+        person: &Person,
+        reminder: &Reminder,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let date = reminder.date.format("%Y-%m-%d").to_string();
+        let values = [
+            ("name", person.name.as_str()),
+            ("phone", person.phone.as_str()),
+            ("email", person.email.as_str()),
+            ("date", date.as_str()),
+            ("task", reminder.task),
+            ("message", reminder.message),
+        ];
+        let mut request = reqwest::Client::new()
+            .post(&self.url)
+            .json(&fill(&self.payload, &values));
+        for (name, value) in &self.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        request = match &self.auth {
+            Some(WebhookAuth::Bearer { token }) => request.bearer_auth(token),
+            Some(WebhookAuth::Basic { username, password }) => {
+                request.basic_auth(username, Some(password))
+            }
+            None => request,
+        };
+        http_status("Webhook", request.send()?)
+    }
+}
+
+//Replaces the "{{placeholders}}" in every string of the payload. The template is read once, so
+//values containing placeholders are left as they are.
+fn fill(payload: &Value, values: &[(&str, &str)]) -> Value {
+    match payload {
+        Value::String(text) => {
+            let mut filled = String::new();
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find("{{") {
+                filled.push_str(&rest[..start]);
+                let placeholder = &rest[start..];
+                let value = placeholder.find("}}").and_then(|end| {
+                    let name = &placeholder[2..end];
+                    values
+                        .iter()
+                        .find(|(n, _)| *n == name)
+                        .map(|(_, value)| (*value, end + 2))
+                });
+                match value {
+                    Some((value, length)) => {
+                        filled.push_str(value);
+                        rest = &placeholder[length..];
+                    }
+                    None => {
+                        filled.push_str("{{");
+                        rest = &placeholder[2..];
+                    }
+                }
+            }
+            filled.push_str(rest);
+            Value::String(filled)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|v| fill(v, values)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(k, v)| (k.clone(), fill(v, values)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fake_server::{body, header, serve};
+
+    #[test]
+    fn post_payload() {
+        let (url, handle) = serve(vec![
+            ("200 OK", "{}"),
+            ("500 Internal Server Error", "{}"),
+            ("204 No Content", ""),
+        ]);
+        let mut webhook = Webhook {
+            url: format!("{}/hook", url),
+            payload: serde_json::json!({
+                "text": "{{task}} on {{date}}: {{message}}",
+                "to": ["{{name}}", "{{email}}"],
+                "urgent": true,
+            }),
+            headers: [("X-Source".to_string(), "cleaning".to_string())].into(),
+            auth: Some(WebhookAuth::Bearer {
+                token: "secret".to_string(),
+            }),
+        };
+        let person = Person {
+            name: "Ann".to_string(),
+            email: "ann@example.com".to_string(),
+            ..Default::default()
+        };
+        let reminder = Reminder {
+            task: "kitchen",
+            date: chrono::NaiveDate::from_ymd_opt(2024, 3, 5).unwrap(),
+            message: "Ann: clean \"now\"",
+        };
+        assert_eq!(webhook.send_reminder(&person, &reminder).unwrap(), "200 OK");
+        assert_eq!(
+            webhook
+                .send_reminder(&person, &reminder)
+                .unwrap_err()
+                .to_string(),
+            "Webhook replied 500 Internal Server Error"
+        );
+        webhook.auth = Some(WebhookAuth::Basic {
+            username: "user".to_string(),
+            password: "pass".to_string(),
+        });
+        assert_eq!(
+            webhook.send_reminder(&person, &reminder).unwrap(),
+            "204 No Content"
+        );
+
+        let requests = handle.join().unwrap();
+        assert!(requests[0].starts_with("POST /hook "));
+        assert_eq!(
+            header(&requests[0], "x-source").as_deref(),
+            Some("cleaning")
+        );
+        assert_eq!(
+            header(&requests[0], "authorization").as_deref(),
+            Some("Bearer secret")
+        );
+        assert_eq!(
+            header(&requests[0], "content-type").as_deref(),
+            Some("application/json")
+        );
+        assert_eq!(
+            header(&requests[2], "authorization").as_deref(),
+            Some("Basic dXNlcjpwYXNz")
+        );
+        assert_eq!(
+            body(&requests[0]),
+            serde_json::json!({
+                "text": "kitchen on 2024-03-05: Ann: clean \"now\"",
+                "to": ["Ann", "ann@example.com"],
+                "urgent": true,
+            })
+        );
+    }
+
+    #[test]
+    fn placeholders_in_values_are_kept() {
+        let payload =
+            serde_json::json!({"text": "{{name}}: {{message}} ({{task}}) {{unknown}} {{"});
+        let filled = fill(
+            &payload,
+            &[
+                ("name", "{{message}}"),
+                ("message", "clean {{task}}"),
+                ("task", "kitchen"),
+            ],
+        );
+        assert_eq!(
+            filled,
+            serde_json::json!({"text": "{{message}}: clean {{task}} (kitchen) {{unknown}} {{"})
+        );
+    }
+}